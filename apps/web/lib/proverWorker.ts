@@ -0,0 +1,92 @@
+/// <reference lib="webworker" />
+// apps/web/lib/proverWorker.ts
+// UI 스레드를 막지 않도록 Web Worker 안에서 증명을 생성합니다.
+// 메인 스레드에서는 proverWorkerClient.ts를 통해 사용합니다.
+import type * as Wasm from 'zk-circuits';
+
+/** 키 버퍼: 전송(transfer)된 ArrayBuffer 또는 공유된 SharedArrayBuffer */
+type KeyBuffer = ArrayBuffer | SharedArrayBuffer;
+
+export type ProverWorkerRequest =
+  | { type: 'init'; pk: KeyBuffer; vk: KeyBuffer; params: KeyBuffer }
+  | {
+      type: 'prove';
+      id: number;
+      inputsJson: string;
+      /** Int32Array(SharedArrayBuffer)의 0번 값이 1이 되면 취소 */
+      cancelFlag?: Int32Array;
+    };
+
+export type ProverWorkerResponse =
+  | { type: 'ready'; treeHeight: number }
+  | {
+      type: 'progress';
+      id: number;
+      phase: Wasm.ProvePhase;
+      step: number;
+      totalSteps: number;
+    }
+  | { type: 'proof'; id: number; proof: ArrayBuffer }
+  | {
+      type: 'error';
+      id?: number;
+      message: string;
+      /** GachaCircuitError의 code/kind/details (WASM에서 발생한 에러인 경우) */
+      code?: Wasm.GachaErrorCode;
+      kind?: string;
+      details?: Record<string, string>;
+    };
+
+const ctx = self as unknown as DedicatedWorkerGlobalScope;
+let prover: Wasm.WorkerProver | null = null;
+
+const post = (message: ProverWorkerResponse, transfer: Transferable[] = []) =>
+  ctx.postMessage(message, transfer);
+
+ctx.onmessage = async (event: MessageEvent<ProverWorkerRequest>) => {
+  const request = event.data;
+  try {
+    if (request.type === 'init') {
+      const wasm = await import('zk-circuits');
+      if (typeof wasm.default === 'function') {
+        await wasm.default();
+      }
+      prover?.free();
+      prover = new wasm.WorkerProver(request.pk, request.vk, request.params);
+      post({ type: 'ready', treeHeight: prover.treeHeight });
+      return;
+    }
+
+    if (!prover) {
+      throw new Error('Prover worker is not initialized');
+    }
+    const { id, inputsJson, cancelFlag } = request;
+    // 증명 중에는 메시지를 받을 수 없으므로, 취소 여부는 공유 메모리로 확인합니다.
+    const cancelToken = cancelFlag
+      ? {
+          get cancelled() {
+            return Atomics.load(cancelFlag, 0) === 1;
+          },
+        }
+      : undefined;
+    const proof = prover.proveSerialized(
+      inputsJson,
+      (phase, step, totalSteps) =>
+        post({ type: 'progress', id, phase, step, totalSteps }),
+      cancelToken,
+    );
+    // 증명 버퍼는 복사 없이 메인 스레드로 전송합니다.
+    post({ type: 'proof', id, proof }, [proof]);
+  } catch (error) {
+    // Error 객체는 구조화 복제 시 추가 속성을 잃으므로 code 등을 따로 보냅니다.
+    const { code, kind, details } = (error ?? {}) as Partial<Wasm.GachaCircuitError>;
+    post({
+      type: 'error',
+      id: request.type === 'prove' ? request.id : undefined,
+      message: error instanceof Error ? error.message : String(error),
+      code,
+      kind,
+      details,
+    });
+  }
+};
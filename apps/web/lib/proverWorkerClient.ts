@@ -0,0 +1,109 @@
+// apps/web/lib/proverWorkerClient.ts
+// proverWorker.ts를 메인 스레드에서 사용하기 위한 클라이언트입니다.
+import type {
+  ProverWorkerRequest,
+  ProverWorkerResponse,
+} from './proverWorker';
+import type { ProveProgressCallback } from './wasmLoader';
+
+/** 워커의 에러 응답을 메인 스레드의 Error(GachaCircuitError 형태)로 되돌립니다. */
+const toError = (
+  message: Extract<ProverWorkerResponse, { type: 'error' }>,
+): Error => {
+  const error = new Error(message.message);
+  if (message.code) {
+    Object.assign(error, {
+      name: 'GachaCircuitError',
+      code: message.code,
+      kind: message.kind,
+      details: message.details,
+    });
+  }
+  return error;
+};
+
+export interface ProverWorkerClient {
+  /** 증명을 생성합니다. `signal`이 abort되면 다음 단계 시작 전에 중단됩니다. */
+  prove: (
+    inputs: object,
+    onProgress?: ProveProgressCallback,
+    signal?: AbortSignal,
+  ) => Promise<Uint8Array>;
+  /** 워커와 워커가 가진 키를 해제합니다. */
+  terminate: () => void;
+}
+
+/**
+ * 증명용 워커를 생성하고 키를 전달합니다.
+ * 키 버퍼는 워커로 전송(transfer)되므로 호출 후 메인 스레드에서는 사용할 수 없습니다.
+ * SharedArrayBuffer를 전달하면 복사 없이 공유됩니다.
+ * @param pk 증명 키 파일 (gacha_pk.bin)
+ * @param vk 검증 키 파일 (gacha_vk.bin)
+ * @param params Poseidon 파라미터 파일 (params.bin)
+ */
+export const createProverWorker = async (
+  pk: ArrayBuffer | SharedArrayBuffer,
+  vk: ArrayBuffer | SharedArrayBuffer,
+  params: ArrayBuffer | SharedArrayBuffer,
+): Promise<ProverWorkerClient> => {
+  const worker = new Worker(new URL('./proverWorker.ts', import.meta.url), {
+    type: 'module',
+  });
+  const send = (request: ProverWorkerRequest, transfer: Transferable[] = []) =>
+    worker.postMessage(request, transfer);
+
+  await new Promise<void>((resolve, reject) => {
+    worker.onmessage = (event: MessageEvent<ProverWorkerResponse>) => {
+      if (event.data.type === 'ready') resolve();
+      if (event.data.type === 'error') reject(toError(event.data));
+    };
+    const transfer = [pk, vk, params].filter(
+      (buffer): buffer is ArrayBuffer => buffer instanceof ArrayBuffer,
+    );
+    send({ type: 'init', pk, vk, params }, transfer);
+  });
+
+  let nextId = 0;
+  const pending = new Map<
+    number,
+    {
+      resolve: (proof: Uint8Array) => void;
+      reject: (error: Error) => void;
+      onProgress?: ProveProgressCallback;
+    }
+  >();
+  worker.onmessage = (event: MessageEvent<ProverWorkerResponse>) => {
+    const message = event.data;
+    if (message.type === 'ready' || message.id === undefined) return;
+    const request = pending.get(message.id);
+    if (!request) return;
+    if (message.type === 'progress') {
+      request.onProgress?.(message.phase, message.step, message.totalSteps);
+      return;
+    }
+    pending.delete(message.id);
+    if (message.type === 'proof') {
+      request.resolve(new Uint8Array(message.proof));
+    } else {
+      request.reject(toError(message));
+    }
+  };
+
+  return {
+    prove: (inputs, onProgress, signal) =>
+      new Promise((resolve, reject) => {
+        const id = nextId++;
+        pending.set(id, { resolve, reject, onProgress });
+        // 교차 출처 격리(crossOriginIsolated) 환경에서만 공유 메모리로 취소할 수 있습니다.
+        let cancelFlag: Int32Array | undefined;
+        if (signal && typeof SharedArrayBuffer !== 'undefined') {
+          const flag = new Int32Array(new SharedArrayBuffer(4));
+          if (signal.aborted) Atomics.store(flag, 0, 1);
+          signal.addEventListener('abort', () => Atomics.store(flag, 0, 1));
+          cancelFlag = flag;
+        }
+        send({ type: 'prove', id, inputsJson: JSON.stringify(inputs), cancelFlag });
+      }),
+    terminate: () => worker.terminate(),
+  };
+};
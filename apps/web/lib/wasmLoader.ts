@@ -1,5 +1,26 @@
 // apps/web/lib/wasmLoader.ts
 import type * as Wasm from 'zk-circuits'; // WASM 패키지 타입 임포트
+import type {
+  CancellationToken,
+  GachaCircuitError,
+  ProveProgressCallback,
+  WasmGachaCircuitInputs,
+  WasmMerkleLeafInputs,
+} from 'zk-circuits';
+
+// Rust DTO에서 생성된 타입(zk_circuits.d.ts)을 그대로 다시 내보냅니다.
+export type {
+  BatchVerificationResult,
+  CancellationToken,
+  FieldEncoding,
+  GachaCircuitError,
+  GachaErrorCode,
+  GachaTreeOutput,
+  ProvePhase,
+  ProveProgressCallback,
+  WasmGachaCircuitInputs,
+  WasmMerkleLeafInputs,
+} from 'zk-circuits';
 
 // 로드된 WASM 모듈 타입을 위한 별칭
 type WasmModule = typeof Wasm;
@@ -84,6 +105,16 @@ export const getLoadedWasmModule = (): WasmModule => {
   return wasmModule;
 };
 
+/**
+ * WASM 함수가 던진 에러인지 확인합니다. `error.code`로 분기하세요 (메시지 문자열은 바뀔 수 있습니다).
+ */
+export const isGachaCircuitError = (
+  error: unknown,
+): error is GachaCircuitError =>
+  error instanceof Error &&
+  error.name === 'GachaCircuitError' &&
+  typeof (error as Partial<GachaCircuitError>).code === 'string';
+
 // --- 특정 WASM 함수 내보내기 (편의를 위해) ---
 // 이 함수들은 모듈이 로드되었는지 확인한 후 내보낸 함수에 접근합니다.
 
@@ -109,21 +140,11 @@ export const initGachaKeys = async (
 
 /**
  * 제공된 입력에 대한 ZK-SNARK 증명을 생성합니다.
- * @param inputs WasmGachaCircuitInputs와 일치하는 JavaScript 객체
+ * @param inputs 회로 입력 (`fieldEncoding` 생략 시 64자리 리틀 엔디언 헥스, 모듈러스 미만만 허용)
  * @returns 직렬화된 증명 바이트 배열
  */
-// Define input type for generateGachaProof
-interface GachaCircuitInputs {
-  merkleRoot: string;
-  itemIdHex: string;
-  secretKeyHex: string;
-  merklePathNodesHex: string[];
-  leafSiblingHashHex: string;
-  leafIndex: number;
-}
-
 export const generateGachaProof = async (
-  inputs: GachaCircuitInputs,
+  inputs: WasmGachaCircuitInputs,
 ): Promise<Uint8Array> => {
   const wasm = await loadWasmModule();
   if (typeof wasm.generate_gacha_proof !== 'function') {
@@ -134,6 +155,29 @@ export const generateGachaProof = async (
   return wasm.generate_gacha_proof(inputs);
 };
 
+/**
+ * 진행 상황 보고와 취소를 지원하는 증명 생성 함수입니다.
+ * 각 단계가 시작되기 전에 `onProgress`가 호출되고, 그 후 `cancelToken.cancelled`를 확인합니다.
+ * 취소되면 "Proof generation was cancelled" 에러로 거부됩니다.
+ * @param inputs 회로 입력
+ * @param onProgress 단계별 진행 콜백 (선택)
+ * @param cancelToken 취소 토큰 (선택)
+ * @returns 직렬화된 증명 바이트 배열
+ */
+export const generateGachaProofWithProgress = async (
+  inputs: WasmGachaCircuitInputs,
+  onProgress?: ProveProgressCallback,
+  cancelToken?: CancellationToken,
+): Promise<Uint8Array> => {
+  const wasm = await loadWasmModule();
+  if (typeof wasm.generate_gacha_proof_with_progress !== 'function') {
+    throw new Error(
+      "WASM module does not export 'generate_gacha_proof_with_progress'",
+    );
+  }
+  return wasm.generate_gacha_proof_with_progress(inputs, onProgress, cancelToken);
+};
+
 /**
  * 제공된 공개 입력 및 증명을 검증합니다.
  * @param merkleRootHex 공개 Merkle 루트 (헥스 문자열)
@@ -151,3 +195,108 @@ export const verifyGachaProof = async (
   // WASM 함수 호출
   return wasm.verify_gacha_proof(merkleRootHex, proofBytes);
 };
+
+/**
+ * 아이템 파일(item_{index}.json)이 Merkle 루트에 속하는지 증명 생성 없이 즉시 확인합니다.
+ * "default" 풀의 Poseidon 파라미터와 트리 높이를 사용합니다.
+ * @param merkleRootHex 공개된 Merkle 루트
+ * @param item 아이템 파일의 내용 (루트 제외)
+ * @returns 경로가 루트로 이어지면 true, 그렇지 않으면 false (경로 길이가 맞지 않으면 에러)
+ */
+export const verifyMerklePath = async (
+  merkleRootHex: string,
+  item: WasmMerkleLeafInputs,
+): Promise<boolean> => {
+  const wasm = await loadWasmModule();
+  if (typeof wasm.verify_merkle_path !== 'function') {
+    throw new Error("WASM module does not export 'verify_merkle_path'");
+  }
+  return wasm.verify_merkle_path(merkleRootHex, item);
+};
+
+/**
+ * 비밀 키와 아이템 ID로 Merkle 리프 다이제스트(Poseidon)를 계산합니다. 디버깅용입니다.
+ * @returns 리프 다이제스트 (64자리 리틀 엔디언 헥스)
+ */
+export const computeLeafDigest = async (
+  secretKeyHex: string,
+  itemIdHex: string,
+): Promise<string> => {
+  const wasm = await loadWasmModule();
+  return wasm.compute_leaf_digest(secretKeyHex, itemIdHex);
+};
+
+/**
+ * 증명과 공개 입력, 회로 ID, 풀 ID, 검증 키 지문을 담은 증명 봉투(binary)를 생성합니다.
+ * @param poolId 증명에 사용할 풀 ID (`init_gacha_keys`로 로드했다면 "default")
+ * @param inputs 회로 입력
+ * @returns 직렬화된 증명 봉투 바이트 배열
+ */
+export const generateProofEnvelope = async (
+  poolId: string,
+  inputs: WasmGachaCircuitInputs,
+): Promise<Uint8Array> => {
+  const wasm = await loadWasmModule();
+  if (typeof wasm.generate_proof_envelope !== 'function') {
+    throw new Error("WASM module does not export 'generate_proof_envelope'");
+  }
+  return wasm.generate_proof_envelope(poolId, inputs);
+};
+
+/**
+ * 증명 봉투를 봉투에 기록된 풀로 검증합니다. Merkle 루트를 따로 전달할 필요가 없습니다.
+ * 다른 키나 다른 회로로 만든 봉투는 false가 아니라 에러로 거부됩니다.
+ * @param envelope 봉투 바이트 배열 또는 JSON 문자열
+ * @returns 증명이 유효하면 true, 그렇지 않으면 false
+ */
+export const verifyProofEnvelope = async (
+  envelope: Uint8Array | string,
+): Promise<boolean> => {
+  const wasm = await loadWasmModule();
+  return typeof envelope === 'string'
+    ? wasm.verify_proof_envelope_json(envelope)
+    : wasm.verify_proof_envelope(envelope);
+};
+
+/**
+ * 자체 키를 소유하는 GachaProver 인스턴스를 생성합니다.
+ * 전역 상태를 사용하지 않으며, 사용이 끝나면 `free()`를 호출해 키 메모리를 해제해야 합니다.
+ * @param pkBytes 직렬화된 증명 키 바이트
+ * @param vkBytes 직렬화된 검증 키 바이트
+ * @param paramsBytes 직렬화된 Poseidon 파라미터 바이트
+ */
+export const createGachaProver = async (
+  pkBytes: Uint8Array,
+  vkBytes: Uint8Array,
+  paramsBytes: Uint8Array,
+): Promise<Wasm.GachaProver> => {
+  const wasm = await loadWasmModule();
+  return new wasm.GachaProver(pkBytes, vkBytes, paramsBytes);
+};
+
+/**
+ * 자체 검증 키를 소유하는 GachaVerifier 인스턴스를 생성합니다.
+ * 사용이 끝나면 `free()`를 호출해 키 메모리를 해제해야 합니다.
+ * @param vkBytes 직렬화된 검증 키 바이트
+ * @param paramsBytes 직렬화된 Poseidon 파라미터 바이트
+ */
+export const createGachaVerifier = async (
+  vkBytes: Uint8Array,
+  paramsBytes: Uint8Array,
+): Promise<Wasm.GachaVerifier> => {
+  const wasm = await loadWasmModule();
+  return new wasm.GachaVerifier(vkBytes, paramsBytes);
+};
+
+/**
+ * 운영자 콘솔용 Merkle 트리 빌더를 생성합니다. 트리 높이는 params.bin에서 읽습니다.
+ * `addLeaf`/`addRandomLeaf`로 리프를 추가한 뒤 `build()`로 루트와 아이템 파일(item_{index}.json 형식)을 얻습니다.
+ * 사용이 끝나면 `free()`를 호출해야 합니다.
+ * @param paramsBytes 직렬화된 Poseidon 파라미터 바이트
+ */
+export const createGachaTreeBuilder = async (
+  paramsBytes: Uint8Array,
+): Promise<Wasm.GachaTreeBuilder> => {
+  const wasm = await loadWasmModule();
+  return new wasm.GachaTreeBuilder(paramsBytes);
+};
@@ -1,8 +1,11 @@
 // apps/web/stores/gachaStore.ts
 import { create } from 'zustand';
 import { createJSONStorage, persist } from 'zustand/middleware';
-// Import types from the WASM package if needed elsewhere, but not directly for WASM calls now
-// import type { WasmGachaCircuitInputs } from 'zk-circuits';
+// Input types are generated from the Rust DTOs (zk_circuits.d.ts)
+import type {
+  WasmGachaCircuitInputs,
+  WasmMerkleLeafInputs,
+} from 'zk-circuits';
 
 // Import functions from the WASM loader
 import {
@@ -21,15 +24,11 @@ export interface ItemDetails {
   rarity?: string;
 }
 
-// Data structure expected from the fetched item_{index}.json file
-interface PullResultData {
-  itemIdHex: string;
-  secretKeyHex: string;
-  merklePathNodesHex: string[];
-  leafSiblingHashHex: string;
-  leafIndex: number;
+// Data structure expected from the fetched item_{index}.json file:
+// the circuit inputs without the shared Merkle root, plus display details
+type PullResultData = WasmMerkleLeafInputs & {
   itemDetails: ItemDetails;
-}
+};
 
 // The complete state managed by Zustand
 export interface GachaState {
@@ -301,7 +300,7 @@ export const useGachaStore = create<GachaState>()(
           // Prepare the input object structure
           // This needs to exactly match the structure expected by the Rust
           // function that uses #[wasm_bindgen] and serde_wasm_bindgen::from_value
-          const wasmInputs = {
+          const wasmInputs: WasmGachaCircuitInputs = {
             merkleRoot: merkleRoot,
             itemIdHex: pullResult.itemIdHex,
             secretKeyHex: pullResult.secretKeyHex,
@@ -0,0 +1,80 @@
+//! Native proving benchmarks for `UserPullCircuit` at several tree heights.
+//!
+//! Compare single-threaded and multi-threaded proving with:
+//!   cargo bench --bench prove -- --save-baseline serial
+//!   cargo bench --bench prove --features parallel -- --baseline serial
+
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_crypto_primitives::merkle_tree::MerkleTree;
+use ark_ff::UniformRand;
+use ark_groth16::{Groth16, ProvingKey};
+use ark_snark::SNARK;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use zk_circuits::{
+    circuit::UserPullCircuit,
+    hash::PoseidonHash,
+    poseidon::default_poseidon_config,
+    prover::{num_threads, prove_pull, prove_pulls},
+    types::{GachaMerkleConfig, NativeGachaCircuitInputs, NativePoseidonConfig},
+};
+
+const TREE_HEIGHTS: [usize; 3] = [4, 8, 16];
+const BATCH_SIZE: usize = 8;
+
+/// Proving key for `tree_height` plus `BATCH_SIZE` valid pulls from one tree.
+fn setup(
+    params: &NativePoseidonConfig,
+    tree_height: usize,
+    rng: &mut StdRng,
+) -> (ProvingKey<Bls12_381>, Vec<NativeGachaCircuitInputs>) {
+    let leaves: Vec<[Fr; 2]> = (0..1usize << tree_height)
+        .map(|_| [Fr::rand(rng), Fr::rand(rng)])
+        .collect();
+    let tree = MerkleTree::<GachaMerkleConfig>::new(params, params, &leaves).unwrap();
+    let inputs = (0..BATCH_SIZE)
+        .map(|i| {
+            let [secret_key, item_id] = leaves[i];
+            NativeGachaCircuitInputs {
+                merkle_root: tree.root(),
+                item_id,
+                secret_key,
+                native_merkle_path: tree.generate_proof(i).unwrap(),
+            }
+        })
+        .collect();
+
+    let circuit = UserPullCircuit::<PoseidonHash>::new(
+        NativeGachaCircuitInputs::placeholder(tree_height),
+        params.clone(),
+    );
+    let (pk, _) = Groth16::<Bls12_381>::circuit_specific_setup(circuit, rng).unwrap();
+    (pk, inputs)
+}
+
+fn bench_prove(c: &mut Criterion) {
+    let params = default_poseidon_config();
+    let mut rng = StdRng::seed_from_u64(0);
+    println!("Proving with {} thread(s)", num_threads());
+
+    let mut group = c.benchmark_group("user_pull_circuit");
+    group.sample_size(10);
+    for tree_height in TREE_HEIGHTS {
+        let (pk, inputs) = setup(&params, tree_height, &mut rng);
+        group.bench_with_input(
+            BenchmarkId::new("prove", tree_height),
+            &inputs,
+            |b, inputs| b.iter(|| prove_pull(&pk, inputs[0].clone(), &params, &mut rng).unwrap()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new(format!("prove_{}", BATCH_SIZE), tree_height),
+            &inputs,
+            |b, inputs| b.iter(|| prove_pulls(&pk, inputs.clone(), &params, &mut rng).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_prove);
+criterion_main!(benches);
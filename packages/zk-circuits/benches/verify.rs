@@ -0,0 +1,66 @@
+//! Native verification benchmarks: `Groth16::verify` with the raw verifying key, which prepares
+//! it on every call, against `verify_with_processed_vk` with a key prepared once, as
+//! `init_gacha_keys` and `init_verifier` now do.
+//!
+//!   cargo bench --bench verify
+
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_crypto_primitives::merkle_tree::MerkleTree;
+use ark_ff::UniformRand;
+use ark_groth16::Groth16;
+use ark_snark::SNARK;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use zk_circuits::{
+    circuit::UserPullCircuit,
+    hash::PoseidonHash,
+    poseidon::default_poseidon_config,
+    prover::prove_pull,
+    types::{prepare_groth16_public_inputs, GachaMerkleConfig, NativeGachaCircuitInputs},
+};
+
+const TREE_HEIGHT: usize = 4;
+
+fn bench_verify(c: &mut Criterion) {
+    let params = default_poseidon_config();
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let leaves: Vec<[Fr; 2]> = (0..1usize << TREE_HEIGHT)
+        .map(|_| [Fr::rand(&mut rng), Fr::rand(&mut rng)])
+        .collect();
+    let tree = MerkleTree::<GachaMerkleConfig>::new(&params, &params, &leaves).unwrap();
+    let [secret_key, item_id] = leaves[0];
+    let inputs = NativeGachaCircuitInputs {
+        merkle_root: tree.root(),
+        item_id,
+        secret_key,
+        native_merkle_path: tree.generate_proof(0).unwrap(),
+    };
+
+    let circuit = UserPullCircuit::<PoseidonHash>::new(
+        NativeGachaCircuitInputs::placeholder(TREE_HEIGHT),
+        params.clone(),
+    );
+    let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(circuit, &mut rng).unwrap();
+    let proof = prove_pull(&pk, inputs, &params, &mut rng).unwrap();
+    let public_inputs = prepare_groth16_public_inputs(tree.root());
+    let pvk = Groth16::<Bls12_381>::process_vk(&vk).unwrap();
+
+    let mut group = c.benchmark_group("user_pull_circuit");
+    group.bench_function("verify_raw_vk", |b| {
+        b.iter(|| assert!(Groth16::<Bls12_381>::verify(&vk, &public_inputs, &proof).unwrap()))
+    });
+    group.bench_function("verify_prepared_vk", |b| {
+        b.iter(|| {
+            assert!(
+                Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &public_inputs, &proof)
+                    .unwrap()
+            )
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify);
+criterion_main!(benches);
@@ -0,0 +1,57 @@
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+use ark_ec::pairing::Pairing;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::vec::Vec;
+
+use super::TargetGroup;
+
+/// A pair of target-group elements committing to vectors under the `a` and `b` halves of the keys.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Commitment {
+    pub t: TargetGroup,
+    pub u: TargetGroup,
+}
+
+impl Commitment {
+    /// Folds `self + left * x + right * x_inv`, mirroring one GIPA round.
+    pub(crate) fn fold(&self, left: &Self, right: &Self, x: &Fr, x_inv: &Fr) -> Self {
+        Self {
+            t: self.t + left.t * x + right.t * x_inv,
+            u: self.u + left.u * x + right.u * x_inv,
+        }
+    }
+}
+
+/// G2 commitment key `v`: `(h^{a^i}, h^{b^i})` for `i < n`.
+#[derive(Clone, Debug)]
+pub(crate) struct VKey {
+    pub a: Vec<G2Affine>,
+    pub b: Vec<G2Affine>,
+}
+
+/// G1 commitment key `w`: `(g^{a^{n+i}}, g^{b^{n+i}})` for `i < n`.
+#[derive(Clone, Debug)]
+pub(crate) struct WKey {
+    pub a: Vec<G1Affine>,
+    pub b: Vec<G1Affine>,
+}
+
+/// Double-pairing commitment to `A` under `v` and `B` under `w`:
+/// `(prod e(A_i, v_a_i) * e(w_a_i, B_i), prod e(A_i, v_b_i) * e(w_b_i, B_i))`.
+pub(crate) fn commit_pair(vkey: &VKey, wkey: &WKey, a: &[G1Affine], b: &[G2Affine]) -> Commitment {
+    let commit = |v: &[G2Affine], w: &[G1Affine]| {
+        Bls12_381::multi_pairing(a.iter().chain(w).copied(), v.iter().chain(b).copied())
+    };
+    Commitment {
+        t: commit(&vkey.a, &wkey.a),
+        u: commit(&vkey.b, &wkey.b),
+    }
+}
+
+/// Single-pairing commitment to the G1 vector `C` under `v`.
+pub(crate) fn commit_g1(vkey: &VKey, c: &[G1Affine]) -> Commitment {
+    Commitment {
+        t: Bls12_381::multi_pairing(c.iter().copied(), vkey.a.iter().copied()),
+        u: Bls12_381::multi_pairing(c.iter().copied(), vkey.b.iter().copied()),
+    }
+}
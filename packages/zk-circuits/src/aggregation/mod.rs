@@ -0,0 +1,95 @@
+//! SnarkPack-style aggregation of many `UserPullCircuit` Groth16 proofs into one proof.
+//!
+//! The aggregator commits to the proof vectors `A`, `B` and `C` with pairing-based commitments
+//! and proves, with the TIPP and MIPP arguments run in a single GIPA recursion, that
+//! `Z_AB = prod_i e(A_i, B_i)^{r^i}` and `Z_C = sum_i r^i * C_i` for a Fiat-Shamir challenge `r`.
+//! The verifier then checks the random linear combination of all Groth16 equations against
+//! `Z_AB` and `Z_C`. Its pairing and group work is logarithmic in the number of proofs;
+//! only folding the public inputs is linear, and that is plain field arithmetic.
+//!
+//! Reference: Gailly, Maller, Nitulescu, "SnarkPack: Practical SNARK Aggregation" (FC 2022).
+
+mod commitment;
+mod proof;
+mod prover;
+mod srs;
+mod verifier;
+
+pub use commitment::Commitment;
+pub use proof::{AggregateProof, GipaRound, KeyOpenings};
+pub use prover::aggregate_proofs;
+pub use srs::{AggregationSrs, ProverSrs, VerifierSrs};
+pub use verifier::verify_aggregate_proof;
+
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_ec::pairing::PairingOutput;
+use ark_ff::{Field, One, Zero};
+use ark_std::vec::Vec;
+
+use crate::error::GachaCircuitError;
+
+/// Element of the pairing target group, written additively as in arkworks.
+pub type TargetGroup = PairingOutput<Bls12_381>;
+
+/// Domain separator fed into the Fiat-Shamir transcript.
+pub(crate) const TRANSCRIPT_DOMAIN: &[u8] = b"zk-gacha-snarkpack-v1";
+
+/// Pads a batch to the next power of two by repeating its last entry.
+/// The recursion halves the vectors each round, so their length must be a power of two.
+pub(crate) fn pad_to_power_of_two<T: Clone>(items: &[T]) -> Result<Vec<T>, GachaCircuitError> {
+    let last = items.last().ok_or_else(|| {
+        GachaCircuitError::InvalidInput("Cannot aggregate an empty set of proofs".to_string())
+    })?;
+    let mut padded = items.to_vec();
+    padded.resize(items.len().next_power_of_two(), last.clone());
+    Ok(padded)
+}
+
+/// Returns `[1, r, r^2, ..., r^{n-1}]`.
+pub(crate) fn powers(r: Fr, n: usize) -> Vec<Fr> {
+    let mut powers = Vec::with_capacity(n);
+    let mut current = Fr::one();
+    for _ in 0..n {
+        powers.push(current);
+        current *= r;
+    }
+    powers
+}
+
+/// Evaluates `prod_j (1 + coeffs[j] * z^{n / 2^{j+1}})`, the polynomial that describes how
+/// GIPA folds a structured vector of length `n` when round `j` uses the coefficient `coeffs[j]`.
+pub(crate) fn evaluate_folding_polynomial(coeffs: &[Fr], z: Fr) -> Fr {
+    // Round j halves a vector of length n / 2^j, so its exponent is z^{2^{k-1-j}}
+    let mut result = Fr::one();
+    let mut z_power = z;
+    for c in coeffs.iter().rev() {
+        result *= Fr::one() + *c * z_power;
+        z_power.square_in_place();
+    }
+    result
+}
+
+/// Expands the folding polynomial of `evaluate_folding_polynomial` into its `n` coefficients.
+pub(crate) fn folding_polynomial_coefficients(coeffs: &[Fr]) -> Vec<Fr> {
+    let mut poly = Vec::with_capacity(1 << coeffs.len());
+    poly.push(Fr::one());
+    for c in coeffs.iter().rev() {
+        let scaled: Vec<Fr> = poly.iter().map(|v| *v * c).collect();
+        poly.extend(scaled);
+    }
+    poly
+}
+
+/// Divides `f(X) - f(z)` by `X - z` and returns the quotient's coefficients.
+pub(crate) fn kzg_quotient(coeffs: &[Fr], z: Fr) -> Vec<Fr> {
+    if coeffs.len() < 2 {
+        return Vec::new();
+    }
+    let mut quotient = vec![Fr::zero(); coeffs.len() - 1];
+    let mut carry = Fr::zero();
+    for i in (1..coeffs.len()).rev() {
+        carry = coeffs[i] + carry * z;
+        quotient[i - 1] = carry;
+    }
+    quotient
+}
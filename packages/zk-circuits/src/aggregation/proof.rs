@@ -0,0 +1,52 @@
+use ark_bls12_381::{G1Affine, G2Affine};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::vec::Vec;
+
+use super::{Commitment, TargetGroup};
+
+/// Cross terms sent in one round of the combined TIPP/MIPP recursion.
+/// `*_l` pairs the right half of the left-hand vector with the left half of the right-hand one.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GipaRound {
+    pub com_ab_l: Commitment,
+    pub com_ab_r: Commitment,
+    pub z_ab_l: TargetGroup,
+    pub z_ab_r: TargetGroup,
+    pub com_c_l: Commitment,
+    pub com_c_r: Commitment,
+    pub z_c_l: G1Affine,
+    pub z_c_r: G1Affine,
+}
+
+/// KZG openings proving the final commitment keys were folded correctly.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KeyOpenings {
+    pub v_a: G2Affine,
+    pub v_b: G2Affine,
+    pub w_a: G1Affine,
+    pub w_b: G1Affine,
+}
+
+/// A single proof standing for a whole (padded) batch of `UserPullCircuit` proofs.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AggregateProof {
+    /// Number of aggregated proofs after padding to a power of two
+    pub num_proofs: u64,
+    /// Commitment to the `A` and `B` vectors
+    pub com_ab: Commitment,
+    /// Commitment to the `C` vector
+    pub com_c: Commitment,
+    /// `prod_i e(A_i, B_i)^{r^i}`
+    pub ip_ab: TargetGroup,
+    /// `sum_i r^i * C_i`
+    pub agg_c: G1Affine,
+    pub rounds: Vec<GipaRound>,
+    pub final_a: G1Affine,
+    pub final_b: G2Affine,
+    pub final_c: G1Affine,
+    /// Folded `(v_a, v_b)` commitment key
+    pub final_vkey: (G2Affine, G2Affine),
+    /// Folded `(w_a, w_b)` commitment key
+    pub final_wkey: (G1Affine, G1Affine),
+    pub openings: KeyOpenings,
+}
@@ -0,0 +1,242 @@
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, Zero};
+use ark_groth16::Proof;
+use ark_std::vec::Vec;
+
+use super::commitment::{commit_g1, commit_pair, VKey, WKey};
+use super::{
+    folding_polynomial_coefficients, kzg_quotient, pad_to_power_of_two, powers, AggregateProof,
+    GipaRound, KeyOpenings, ProverSrs, TRANSCRIPT_DOMAIN,
+};
+use crate::error::GachaCircuitError;
+use crate::types::ConstraintField;
+use crate::utils::transcript::Transcript;
+
+/// Aggregates `proofs[i]` (each proving membership under `merkle_roots[i]`) into one proof.
+/// Batches whose size is not a power of two are padded by repeating the last proof.
+pub fn aggregate_proofs(
+    srs: &ProverSrs,
+    merkle_roots: &[ConstraintField],
+    proofs: &[Proof<Bls12_381>],
+) -> Result<AggregateProof, GachaCircuitError> {
+    if merkle_roots.len() != proofs.len() {
+        return Err(GachaCircuitError::InvalidInput(format!(
+            "Aggregation length mismatch: {} roots but {} proofs",
+            merkle_roots.len(),
+            proofs.len()
+        )));
+    }
+    let merkle_roots = pad_to_power_of_two(merkle_roots)?;
+    let proofs = pad_to_power_of_two(proofs)?;
+    let n = proofs.len();
+    if n != srs.n {
+        return Err(GachaCircuitError::InvalidInput(format!(
+            "Prover SRS is specialized for {} proofs, got {} after padding",
+            srs.n, n
+        )));
+    }
+
+    let a: Vec<G1Affine> = proofs.iter().map(|p| p.a).collect();
+    let b: Vec<G2Affine> = proofs.iter().map(|p| p.b).collect();
+    let c: Vec<G1Affine> = proofs.iter().map(|p| p.c).collect();
+
+    // 1. Commit to A, B and C, then derive the combination challenge r
+    let com_ab = commit_pair(&srs.vkey, &srs.wkey, &a, &b);
+    let com_c = commit_g1(&srs.vkey, &c);
+
+    let mut transcript = Transcript::new(TRANSCRIPT_DOMAIN);
+    transcript.append(b"num_proofs", &(n as u64))?;
+    transcript.append(b"merkle_roots", &merkle_roots)?;
+    transcript.append(b"com_ab", &com_ab)?;
+    transcript.append(b"com_c", &com_c)?;
+    let r = transcript.challenge(b"r");
+    let r_inv = r.inverse().expect("challenges are non-zero");
+
+    // 2. Fold the Groth16 terms: Z_AB = prod e(A_i, B_i^{r^i}), Z_C = sum r^i C_i.
+    // Scaling B by r^i and w by r^{-i} leaves the commitment to B unchanged.
+    let r_powers = powers(r, n);
+    let b_r = G2Projective::normalize_batch(
+        &b.iter()
+            .zip(&r_powers)
+            .map(|(b, r)| *b * r)
+            .collect::<Vec<_>>(),
+    );
+    let r_inv_powers = powers(r_inv, n);
+    let wkey_r = WKey {
+        a: scale_g1(&srs.wkey.a, &r_inv_powers),
+        b: scale_g1(&srs.wkey.b, &r_inv_powers),
+    };
+    let ip_ab = Bls12_381::multi_pairing(a.iter().copied(), b_r.iter().copied());
+    let agg_c = msm_g1(&c, &r_powers)?.into_affine();
+    transcript.append(b"ip_ab", &ip_ab)?;
+    transcript.append(b"agg_c", &agg_c)?;
+
+    // 3. Run the combined TIPP/MIPP recursion, halving every vector each round
+    let mut m_a = a;
+    let mut m_b = b_r;
+    let mut m_c = c;
+    let mut m_r = r_powers;
+    let mut vkey = srs.vkey.clone();
+    let mut wkey = wkey_r;
+    let mut rounds = Vec::new();
+    let mut challenges = Vec::new();
+
+    while m_a.len() > 1 {
+        let split = m_a.len() / 2;
+        let (a_l, a_r) = m_a.split_at(split);
+        let (b_l, b_r) = m_b.split_at(split);
+        let (c_l, c_r) = m_c.split_at(split);
+        let (r_l, r_r) = m_r.split_at(split);
+        let (vk_l, vk_r) = vkey.split_at(split);
+        let (wk_l, wk_r) = wkey.split_at(split);
+
+        let round = GipaRound {
+            com_ab_l: commit_pair(&vk_l, &wk_r, a_r, b_l),
+            com_ab_r: commit_pair(&vk_r, &wk_l, a_l, b_r),
+            z_ab_l: Bls12_381::multi_pairing(a_r.iter().copied(), b_l.iter().copied()),
+            z_ab_r: Bls12_381::multi_pairing(a_l.iter().copied(), b_r.iter().copied()),
+            com_c_l: commit_g1(&vk_l, c_r),
+            com_c_r: commit_g1(&vk_r, c_l),
+            z_c_l: msm_g1(c_r, r_l)?.into_affine(),
+            z_c_r: msm_g1(c_l, r_r)?.into_affine(),
+        };
+        transcript.append(b"gipa_round", &round)?;
+        let x = transcript.challenge(b"x");
+        let x_inv = x.inverse().expect("challenges are non-zero");
+
+        let next_a = fold_g1(a_l, a_r, &x);
+        let next_b = fold_g2(b_l, b_r, &x_inv);
+        let next_c = fold_g1(c_l, c_r, &x);
+        let next_r = r_l.iter().zip(r_r).map(|(l, r)| *l + *r * x_inv).collect();
+        vkey = VKey {
+            a: fold_g2(&vk_l.a, &vk_r.a, &x_inv),
+            b: fold_g2(&vk_l.b, &vk_r.b, &x_inv),
+        };
+        wkey = WKey {
+            a: fold_g1(&wk_l.a, &wk_r.a, &x),
+            b: fold_g1(&wk_l.b, &wk_r.b, &x),
+        };
+        m_a = next_a;
+        m_b = next_b;
+        m_c = next_c;
+        m_r = next_r;
+        rounds.push(round);
+        challenges.push(x);
+    }
+
+    let final_vkey = (vkey.a[0], vkey.b[0]);
+    let final_wkey = (wkey.a[0], wkey.b[0]);
+    transcript.append(b"final_a", &m_a[0])?;
+    transcript.append(b"final_b", &m_b[0])?;
+    transcript.append(b"final_c", &m_c[0])?;
+    transcript.append(b"final_vkey", &final_vkey)?;
+    transcript.append(b"final_wkey", &final_wkey)?;
+    let z = transcript.challenge(b"z");
+
+    // 4. Open the folded keys at z: v folds with x_j^{-1}; w folds with x_j * r^{-n/2^{j+1}}
+    // and carries an extra X^n factor from starting at g^{a^n}.
+    let v_coeffs: Vec<Fr> = challenges
+        .iter()
+        .map(|x| x.inverse().expect("challenges are non-zero"))
+        .collect();
+    let w_coeffs: Vec<Fr> = challenges
+        .iter()
+        .enumerate()
+        .map(|(j, x)| *x * r_inv.pow([(n >> (j + 1)) as u64]))
+        .collect();
+
+    let v_quotient = kzg_quotient(&folding_polynomial_coefficients(&v_coeffs), z);
+    let mut w_poly = vec![Fr::zero(); n];
+    w_poly.extend(folding_polynomial_coefficients(&w_coeffs));
+    let w_quotient = kzg_quotient(&w_poly, z);
+
+    let openings = KeyOpenings {
+        v_a: msm_g2(&srs.vkey.a[..v_quotient.len()], &v_quotient)?.into_affine(),
+        v_b: msm_g2(&srs.vkey.b[..v_quotient.len()], &v_quotient)?.into_affine(),
+        w_a: msm_g1(&srs.g_a_powers[..w_quotient.len()], &w_quotient)?.into_affine(),
+        w_b: msm_g1(&srs.g_b_powers[..w_quotient.len()], &w_quotient)?.into_affine(),
+    };
+
+    Ok(AggregateProof {
+        num_proofs: n as u64,
+        com_ab,
+        com_c,
+        ip_ab,
+        agg_c,
+        rounds,
+        final_a: m_a[0],
+        final_b: m_b[0],
+        final_c: m_c[0],
+        final_vkey,
+        final_wkey,
+        openings,
+    })
+}
+
+impl VKey {
+    fn split_at(&self, mid: usize) -> (Self, Self) {
+        let (a_l, a_r) = self.a.split_at(mid);
+        let (b_l, b_r) = self.b.split_at(mid);
+        (
+            Self {
+                a: a_l.to_vec(),
+                b: b_l.to_vec(),
+            },
+            Self {
+                a: a_r.to_vec(),
+                b: b_r.to_vec(),
+            },
+        )
+    }
+}
+
+impl WKey {
+    fn split_at(&self, mid: usize) -> (Self, Self) {
+        let (a_l, a_r) = self.a.split_at(mid);
+        let (b_l, b_r) = self.b.split_at(mid);
+        (
+            Self {
+                a: a_l.to_vec(),
+                b: b_l.to_vec(),
+            },
+            Self {
+                a: a_r.to_vec(),
+                b: b_r.to_vec(),
+            },
+        )
+    }
+}
+
+/// Computes `left_i + right_i * x` for each i.
+fn fold_g1(left: &[G1Affine], right: &[G1Affine], x: &Fr) -> Vec<G1Affine> {
+    let folded: Vec<G1Projective> = left.iter().zip(right).map(|(l, r)| *r * x + l).collect();
+    G1Projective::normalize_batch(&folded)
+}
+
+/// Computes `left_i + right_i * x` for each i.
+fn fold_g2(left: &[G2Affine], right: &[G2Affine], x: &Fr) -> Vec<G2Affine> {
+    let folded: Vec<G2Projective> = left.iter().zip(right).map(|(l, r)| *r * x + l).collect();
+    G2Projective::normalize_batch(&folded)
+}
+
+fn scale_g1(points: &[G1Affine], scalars: &[Fr]) -> Vec<G1Affine> {
+    let scaled: Vec<G1Projective> = points.iter().zip(scalars).map(|(p, s)| *p * s).collect();
+    G1Projective::normalize_batch(&scaled)
+}
+
+fn msm_g1(bases: &[G1Affine], scalars: &[Fr]) -> Result<G1Projective, GachaCircuitError> {
+    if bases.is_empty() {
+        return Ok(G1Affine::zero().into_group());
+    }
+    G1Projective::msm(bases, scalars)
+        .map_err(|len| GachaCircuitError::ProofGeneration(format!("MSM length mismatch ({})", len)))
+}
+
+fn msm_g2(bases: &[G2Affine], scalars: &[Fr]) -> Result<G2Projective, GachaCircuitError> {
+    if bases.is_empty() {
+        return Ok(G2Affine::zero().into_group());
+    }
+    G2Projective::msm(bases, scalars)
+        .map_err(|len| GachaCircuitError::ProofGeneration(format!("MSM length mismatch ({})", len)))
+}
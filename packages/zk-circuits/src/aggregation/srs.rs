@@ -0,0 +1,118 @@
+use ark_bls12_381::{Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{PrimeGroup, ScalarMul};
+use ark_ff::UniformRand;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{
+    rand::{CryptoRng, RngCore},
+    vec::Vec,
+};
+
+use super::commitment::{VKey, WKey};
+use super::powers;
+use crate::error::GachaCircuitError;
+
+/// Structured reference string for aggregation: powers of two independent secrets `a` and `b`
+/// in both source groups.
+///
+/// Anyone who knows `a` or `b` can forge aggregate proofs. `setup` samples the secrets locally
+/// and drops them, which is fine for tests and single-party demos; a production SRS must come
+/// from a powers-of-tau ceremony or a party that every verifier trusts to have discarded them.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq)]
+pub struct AggregationSrs {
+    /// `g^{a^i}` for `i < 2 * max_proofs`
+    pub g_a_powers: Vec<G1Affine>,
+    /// `g^{b^i}` for `i < 2 * max_proofs`
+    pub g_b_powers: Vec<G1Affine>,
+    /// `h^{a^i}` for `i < max_proofs`
+    pub h_a_powers: Vec<G2Affine>,
+    /// `h^{b^i}` for `i < max_proofs`
+    pub h_b_powers: Vec<G2Affine>,
+}
+
+/// Prover's view of the SRS, specialized to a fixed (power-of-two) number of proofs.
+#[derive(Clone, Debug)]
+pub struct ProverSrs {
+    pub(crate) n: usize,
+    pub(crate) vkey: VKey,
+    pub(crate) wkey: WKey,
+    pub(crate) g_a_powers: Vec<G1Affine>,
+    pub(crate) g_b_powers: Vec<G1Affine>,
+}
+
+/// Verifier's view of the SRS: the generators and the first power of each secret.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq)]
+pub struct VerifierSrs {
+    pub max_proofs: u64,
+    pub g: G1Affine,
+    pub h: G2Affine,
+    pub g_a: G1Affine,
+    pub g_b: G1Affine,
+    pub h_a: G2Affine,
+    pub h_b: G2Affine,
+}
+
+impl AggregationSrs {
+    /// Samples a fresh SRS able to aggregate up to `max_proofs` proofs (rounded up to a power of two).
+    /// The secrets are only as unpredictable as `rng`, so it must be seeded from the OS.
+    pub fn setup<R: RngCore + CryptoRng>(rng: &mut R, max_proofs: usize) -> Self {
+        let n = max_proofs.max(2).next_power_of_two();
+        let a = Fr::rand(rng);
+        let b = Fr::rand(rng);
+
+        let g = G1Projective::generator();
+        let h = G2Projective::generator();
+        Self {
+            g_a_powers: g.batch_mul(&powers(a, 2 * n)),
+            g_b_powers: g.batch_mul(&powers(b, 2 * n)),
+            h_a_powers: h.batch_mul(&powers(a, n)),
+            h_b_powers: h.batch_mul(&powers(b, n)),
+        }
+    }
+
+    /// Maximum number of proofs this SRS can aggregate.
+    pub fn max_proofs(&self) -> usize {
+        self.h_a_powers.len()
+    }
+
+    /// Specializes the SRS for aggregating exactly `n` proofs (`n` must be a power of two).
+    pub fn prover_srs(&self, n: usize) -> Result<ProverSrs, GachaCircuitError> {
+        if !n.is_power_of_two() || n > self.max_proofs() {
+            return Err(GachaCircuitError::InvalidInput(format!(
+                "Cannot aggregate {} proofs with an SRS for at most {}",
+                n,
+                self.max_proofs()
+            )));
+        }
+        Ok(ProverSrs {
+            n,
+            vkey: VKey {
+                a: self.h_a_powers[..n].to_vec(),
+                b: self.h_b_powers[..n].to_vec(),
+            },
+            wkey: WKey {
+                a: self.g_a_powers[n..2 * n].to_vec(),
+                b: self.g_b_powers[n..2 * n].to_vec(),
+            },
+            g_a_powers: self.g_a_powers[..2 * n].to_vec(),
+            g_b_powers: self.g_b_powers[..2 * n].to_vec(),
+        })
+    }
+
+    /// Extracts the constant-size verifier SRS.
+    pub fn verifier_srs(&self) -> Result<VerifierSrs, GachaCircuitError> {
+        if self.h_a_powers.len() < 2 || self.g_a_powers.len() < 2 {
+            return Err(GachaCircuitError::SetupError(
+                "Aggregation SRS is too short".to_string(),
+            ));
+        }
+        Ok(VerifierSrs {
+            max_proofs: self.max_proofs() as u64,
+            g: self.g_a_powers[0],
+            h: self.h_a_powers[0],
+            g_a: self.g_a_powers[1],
+            g_b: self.g_b_powers[1],
+            h_a: self.h_a_powers[1],
+            h_b: self.h_b_powers[1],
+        })
+    }
+}
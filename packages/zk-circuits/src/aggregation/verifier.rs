@@ -0,0 +1,164 @@
+use ark_bls12_381::{Bls12_381, Fr, G1Affine, G2Affine};
+use ark_ec::{
+    pairing::{Pairing, PairingOutput},
+    AffineRepr, CurveGroup,
+};
+use ark_ff::{Field, Zero};
+use ark_groth16::PreparedVerifyingKey;
+use ark_std::vec::Vec;
+
+use super::{
+    evaluate_folding_polynomial, pad_to_power_of_two, powers, AggregateProof, TargetGroup,
+    VerifierSrs, TRANSCRIPT_DOMAIN,
+};
+use crate::batch::combine_public_inputs;
+use crate::error::GachaCircuitError;
+use crate::types::ConstraintField;
+use crate::utils::transcript::Transcript;
+
+/// Verifies an aggregate proof for the pulls under `merkle_roots` (in the order they were aggregated).
+///
+/// Returns `Ok(false)` if any check fails and an error only for malformed input.
+pub fn verify_aggregate_proof(
+    srs: &VerifierSrs,
+    pvk: &PreparedVerifyingKey<Bls12_381>,
+    merkle_roots: &[ConstraintField],
+    proof: &AggregateProof,
+) -> Result<bool, GachaCircuitError> {
+    // The verifier pads the roots the same way the prover padded its batch.
+    let merkle_roots = pad_to_power_of_two(merkle_roots)?;
+    let n = merkle_roots.len();
+    if proof.num_proofs != n as u64 || proof.num_proofs > srs.max_proofs {
+        return Err(GachaCircuitError::InvalidInput(format!(
+            "Aggregate proof covers {} proofs but {} roots were supplied (SRS maximum {})",
+            proof.num_proofs, n, srs.max_proofs
+        )));
+    }
+    if proof.rounds.len() != n.trailing_zeros() as usize {
+        return Err(GachaCircuitError::InvalidInput(format!(
+            "Aggregate proof has {} rounds, expected {}",
+            proof.rounds.len(),
+            n.trailing_zeros()
+        )));
+    }
+
+    // 1. Replay the transcript
+    let mut transcript = Transcript::new(TRANSCRIPT_DOMAIN);
+    transcript.append(b"num_proofs", &proof.num_proofs)?;
+    transcript.append(b"merkle_roots", &merkle_roots)?;
+    transcript.append(b"com_ab", &proof.com_ab)?;
+    transcript.append(b"com_c", &proof.com_c)?;
+    let r = transcript.challenge(b"r");
+    let r_inv = r.inverse().expect("challenges are non-zero");
+    transcript.append(b"ip_ab", &proof.ip_ab)?;
+    transcript.append(b"agg_c", &proof.agg_c)?;
+
+    // 2. Fold the commitments and inner products with the round challenges
+    let mut com_ab = proof.com_ab;
+    let mut com_c = proof.com_c;
+    let mut z_ab = proof.ip_ab;
+    let mut z_c = proof.agg_c.into_group();
+    let mut challenges = Vec::with_capacity(proof.rounds.len());
+    for round in &proof.rounds {
+        transcript.append(b"gipa_round", round)?;
+        let x = transcript.challenge(b"x");
+        let x_inv = x.inverse().expect("challenges are non-zero");
+
+        com_ab = com_ab.fold(&round.com_ab_l, &round.com_ab_r, &x, &x_inv);
+        com_c = com_c.fold(&round.com_c_l, &round.com_c_r, &x, &x_inv);
+        z_ab += round.z_ab_l * x + round.z_ab_r * x_inv;
+        z_c += round.z_c_l * x + round.z_c_r * x_inv;
+        challenges.push(x);
+    }
+
+    transcript.append(b"final_a", &proof.final_a)?;
+    transcript.append(b"final_b", &proof.final_b)?;
+    transcript.append(b"final_c", &proof.final_c)?;
+    transcript.append(b"final_vkey", &proof.final_vkey)?;
+    transcript.append(b"final_wkey", &proof.final_wkey)?;
+    let z = transcript.challenge(b"z");
+
+    // 3. Check the folded values against the final single elements
+    let (v_a, v_b) = proof.final_vkey;
+    let (w_a, w_b) = proof.final_wkey;
+    let (a, b, c) = (proof.final_a, proof.final_b, proof.final_c);
+    let v_coeffs: Vec<Fr> = challenges
+        .iter()
+        .map(|x| x.inverse().expect("challenges are non-zero"))
+        .collect();
+    let final_r = evaluate_folding_polynomial(&v_coeffs, r);
+
+    let gipa_ok = com_ab.t == pairing_sum(&[a, w_a], &[v_a, b])
+        && com_ab.u == pairing_sum(&[a, w_b], &[v_b, b])
+        && z_ab == Bls12_381::pairing(a, b)
+        && com_c.t == Bls12_381::pairing(c, v_a)
+        && com_c.u == Bls12_381::pairing(c, v_b)
+        && z_c == c * final_r;
+    if !gipa_ok {
+        return Ok(false);
+    }
+
+    // 4. Check the KZG openings of the folded commitment keys at z
+    let w_coeffs: Vec<Fr> = challenges
+        .iter()
+        .enumerate()
+        .map(|(j, x)| *x * r_inv.pow([(n >> (j + 1)) as u64]))
+        .collect();
+    let v_eval = evaluate_folding_polynomial(&v_coeffs, z);
+    let w_eval = z.pow([n as u64]) * evaluate_folding_polynomial(&w_coeffs, z);
+
+    let keys_ok = check_g2_opening(srs, srs.g_a, v_a, proof.openings.v_a, z, v_eval)
+        && check_g2_opening(srs, srs.g_b, v_b, proof.openings.v_b, z, v_eval)
+        && check_g1_opening(srs, srs.h_a, w_a, proof.openings.w_a, z, w_eval)
+        && check_g1_opening(srs, srs.h_b, w_b, proof.openings.w_b, z, w_eval);
+    if !keys_ok {
+        return Ok(false);
+    }
+
+    // 5. Check the combined Groth16 equation:
+    // Z_AB = e(alpha, beta)^{sum r^i} * e(sum r^i IC(x_i), gamma) * e(Z_C, delta)
+    let r_powers = powers(r, n);
+    let r_sum: Fr = r_powers.iter().sum();
+    let combined_ic = combine_public_inputs(pvk, &merkle_roots, &r_powers)?;
+    let expected_ip_ab = PairingOutput::<Bls12_381>(pvk.alpha_g1_beta_g2) * r_sum
+        + pairing_sum(
+            &[combined_ic.into_affine(), proof.agg_c],
+            &[pvk.vk.gamma_g2, pvk.vk.delta_g2],
+        );
+
+    Ok(proof.ip_ab == expected_ip_ab)
+}
+
+fn pairing_sum(g1: &[G1Affine], g2: &[G2Affine]) -> TargetGroup {
+    Bls12_381::multi_pairing(g1.iter().copied(), g2.iter().copied())
+}
+
+/// Checks that `commitment = h^{f(s)}` given `f(z) = eval` and the opening `h^{q(s)}`:
+/// `e(g^s - g^z, opening) = e(g, commitment - h^{eval})`.
+fn check_g2_opening(
+    srs: &VerifierSrs,
+    g_s: G1Affine,
+    commitment: G2Affine,
+    opening: G2Affine,
+    z: Fr,
+    eval: Fr,
+) -> bool {
+    let lhs_g1 = (g_s.into_group() - srs.g * z).into_affine();
+    let rhs_g2 = (commitment.into_group() - srs.h * eval).into_affine();
+    pairing_sum(&[lhs_g1, -srs.g], &[opening, rhs_g2]).is_zero()
+}
+
+/// Checks that `commitment = g^{f(s)}` given `f(z) = eval` and the opening `g^{q(s)}`:
+/// `e(opening, h^s - h^z) = e(commitment - g^{eval}, h)`.
+fn check_g1_opening(
+    srs: &VerifierSrs,
+    h_s: G2Affine,
+    commitment: G1Affine,
+    opening: G1Affine,
+    z: Fr,
+    eval: Fr,
+) -> bool {
+    let lhs_g2 = (h_s.into_group() - srs.h * z).into_affine();
+    let rhs_g1 = (commitment.into_group() - srs.g * eval).into_affine();
+    pairing_sum(&[opening, -rhs_g1], &[lhs_g2, srs.h]).is_zero()
+}
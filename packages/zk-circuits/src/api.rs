@@ -0,0 +1,561 @@
+//! Class-based JS API: `GachaProver` and `GachaVerifier` own their keys instead of going
+//! through the pool registry. On the Rust side they are [`JsGachaProver`] and [`JsGachaVerifier`],
+//! so they are not mistaken for the native types in [`crate::native`]. Instances are independent, so pages can create short-lived ones
+//! and release their keys with `free()` (generated by wasm-bindgen) as soon as they are done.
+//! `GachaTreeBuilder` wraps [`crate::tree::GachaTree`] for building pools in the browser.
+//!
+//! The free functions in [`crate::bindings`] share the JS conversions below with these classes.
+
+use ark_bls12_381::Bls12_381;
+#[cfg(feature = "prover")]
+use ark_crypto_primitives::crh::CRHScheme;
+use ark_groth16::Proof;
+#[cfg(feature = "prover")]
+use ark_serialize::CanonicalSerialize;
+#[cfg(feature = "prover")]
+use ark_std::string::ToString;
+use ark_std::{format, string::String, vec::Vec};
+#[cfg(feature = "prover")]
+use js_sys::{Function, Reflect};
+#[cfg(feature = "prover")]
+use serde::{Deserialize, Serialize};
+use serde_wasm_bindgen::from_value;
+#[cfg(feature = "prover")]
+use tsify::Tsify;
+use wasm_bindgen::prelude::*;
+
+use crate::batch::BatchVerificationResult;
+use crate::envelope::GachaProofEnvelope;
+use crate::error::GachaCircuitError;
+#[cfg(feature = "prover")]
+use crate::hash::{MerkleHash, PoseidonHash};
+use crate::keys::VerifierKeys;
+#[cfg(feature = "prover")]
+use crate::keys::{decode_key_file, poseidon_params_hash, GachaKeys, KeyFileKind};
+use crate::native;
+#[cfg(feature = "prover")]
+use crate::pool::GachaPool;
+#[cfg(feature = "prover")]
+use crate::prover::ProvePhase;
+#[cfg(feature = "prover")]
+use crate::tree::{GachaLeaf, GachaTree, MAX_TREE_HEIGHT};
+use crate::types::{fr_decode, FieldEncoding};
+#[cfg(feature = "prover")]
+use crate::types::{
+    fr_to_hex, leaf_digest, ConstraintField, NativeGachaCircuitInputs, NativePoseidonConfig,
+    WasmGachaCircuitInputs, WasmMerkleLeafInputs,
+};
+#[cfg(feature = "prover")]
+use crate::utils::get_rng;
+use crate::utils::serialization::{deserialize_compressed, ValueKind};
+
+// Types for the untyped (`JsValue` / `Function`) parameters of the proving functions
+#[cfg(feature = "prover")]
+#[wasm_bindgen(typescript_custom_section)]
+const PROVE_PROGRESS_TS: &str = r#"
+/** A proving phase, in order: reported as `(phase, step, totalSteps)`, `step` counting from 0. */
+export type ProvePhase = "synthesis" | "witness_map" | "msm_a" | "msm_b" | "msm_c" | "serialization";
+export type ProveProgressCallback = (phase: ProvePhase, step: number, totalSteps: number) => void;
+/** Proving stops before the next phase once `cancelled` is truthy. */
+export interface CancellationToken {
+  readonly cancelled: boolean;
+}
+"#;
+
+/// Proves (and verifies) pulls with its own proving key, verifying key and parameters.
+/// Wraps [`native::GachaProver`].
+#[cfg(feature = "prover")]
+#[wasm_bindgen(js_name = GachaProver)]
+pub struct JsGachaProver {
+    prover: native::GachaProver,
+}
+
+#[cfg(feature = "prover")]
+#[wasm_bindgen(js_class = GachaProver)]
+impl JsGachaProver {
+    /// Loads `gacha_pk.bin`, `gacha_vk.bin` and `params.bin`, with the same checks as `init_gacha_keys`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        pk_bytes: &[u8],
+        vk_bytes: &[u8],
+        params_bytes: &[u8],
+    ) -> Result<JsGachaProver, JsValue> {
+        let keys = GachaKeys::decode(
+            pk_bytes,
+            vk_bytes,
+            params_bytes,
+            crate::bindings::deserialization_policy(),
+        )?;
+        Ok(Self {
+            prover: native::GachaProver::new(keys)?,
+        })
+    }
+
+    /// Proves a pull and returns the compressed proof bytes.
+    pub fn prove(&self, inputs: WasmGachaCircuitInputs) -> Result<Vec<u8>, JsValue> {
+        Ok(prove_wasm_inputs(
+            &self.prover,
+            inputs,
+            None,
+            ProofEncoding::Raw,
+        )?)
+    }
+
+    /// Proves a pull and returns a binary `GachaProofEnvelope` labelled with `pool_id`.
+    #[wasm_bindgen(js_name = proveEnvelope)]
+    pub fn prove_envelope(
+        &self,
+        inputs: WasmGachaCircuitInputs,
+        pool_id: &str,
+    ) -> Result<Vec<u8>, JsValue> {
+        Ok(prove_wasm_inputs(
+            &self.prover,
+            inputs,
+            None,
+            ProofEncoding::Envelope { pool_id },
+        )?)
+    }
+
+    /// [`JsGachaProver::prove`] with progress reporting and cancellation; same arguments as
+    /// `generate_gacha_proof_with_progress`.
+    #[wasm_bindgen(js_name = proveWithProgress)]
+    pub fn prove_with_progress(
+        &self,
+        inputs: WasmGachaCircuitInputs,
+        #[wasm_bindgen(unchecked_param_type = "ProveProgressCallback | undefined")]
+        on_progress: Option<Function>,
+        #[wasm_bindgen(unchecked_param_type = "CancellationToken | undefined")]
+        cancel_token: JsValue,
+    ) -> Result<Vec<u8>, JsValue> {
+        let progress = JsProgress {
+            on_progress,
+            cancel_token,
+        };
+        Ok(prove_wasm_inputs(
+            &self.prover,
+            inputs,
+            Some(&progress),
+            ProofEncoding::Raw,
+        )?)
+    }
+
+    /// Verifies a proof against a hex Merkle root with this prover's verifying key.
+    pub fn verify(&self, merkle_root_hex: &str, proof_bytes: &[u8]) -> Result<bool, JsValue> {
+        Ok(verify_js(
+            &self.prover.verifier(),
+            merkle_root_hex,
+            proof_bytes,
+        )?)
+    }
+
+    /// Checks an item file against a hex Merkle root without proving; same as `verify_merkle_path`.
+    #[wasm_bindgen(js_name = verifyMerklePath)]
+    pub fn verify_merkle_path(
+        &self,
+        merkle_root_hex: String,
+        inputs: WasmMerkleLeafInputs,
+    ) -> Result<bool, JsValue> {
+        Ok(verify_merkle_path_js(
+            self.prover.pool(),
+            merkle_root_hex,
+            inputs,
+        )?)
+    }
+
+    /// A verifier sharing this prover's keys; it stays valid after the prover is freed.
+    pub fn verifier(&self) -> JsGachaVerifier {
+        JsGachaVerifier {
+            verifier: self.prover.verifier(),
+        }
+    }
+
+    /// The Merkle tree has `2^treeHeight` leaves.
+    #[wasm_bindgen(getter, js_name = treeHeight)]
+    pub fn tree_height(&self) -> usize {
+        self.prover.tree_height()
+    }
+}
+
+/// Verifies pull proofs with its own verifying key and parameters.
+/// Wraps [`native::GachaVerifier`].
+#[wasm_bindgen(js_name = GachaVerifier)]
+pub struct JsGachaVerifier {
+    verifier: native::GachaVerifier,
+}
+
+#[wasm_bindgen(js_class = GachaVerifier)]
+impl JsGachaVerifier {
+    /// Loads `gacha_vk.bin` and `params.bin`, with the same checks as `init_verifier`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(vk_bytes: &[u8], params_bytes: &[u8]) -> Result<JsGachaVerifier, JsValue> {
+        let keys = VerifierKeys::decode(
+            vk_bytes,
+            params_bytes,
+            crate::bindings::deserialization_policy(),
+        )?;
+        Ok(Self {
+            verifier: native::GachaVerifier::new(keys)?,
+        })
+    }
+
+    /// Verifies a proof against a hex Merkle root.
+    pub fn verify(&self, merkle_root_hex: &str, proof_bytes: &[u8]) -> Result<bool, JsValue> {
+        Ok(verify_js(&self.verifier, merkle_root_hex, proof_bytes)?)
+    }
+
+    /// Verifies a binary proof envelope made with this verifier's keys; its pool id is ignored.
+    #[wasm_bindgen(js_name = verifyEnvelope)]
+    pub fn verify_envelope(&self, envelope_bytes: &[u8]) -> Result<bool, JsValue> {
+        let envelope = GachaProofEnvelope::from_bytes(
+            envelope_bytes,
+            crate::bindings::deserialization_policy(),
+        )?;
+        Ok(self.verifier.verify_envelope(&envelope)?)
+    }
+
+    /// Batch-verifies proofs; same arguments and result as `verify_gacha_proofs_batch`.
+    #[wasm_bindgen(js_name = verifyBatch)]
+    pub fn verify_batch(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "string[]")] merkle_roots: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] proofs: JsValue,
+    ) -> Result<BatchVerificationResult, JsValue> {
+        Ok(verify_batch_js(&self.verifier, merkle_roots, proofs)?)
+    }
+
+    /// The Merkle tree has `2^treeHeight` leaves.
+    #[wasm_bindgen(getter, js_name = treeHeight)]
+    pub fn tree_height(&self) -> usize {
+        self.verifier.tree_height()
+    }
+}
+
+/// What [`GachaTreeBuilder::build`] returns: the root to publish and one item file per added leaf.
+#[cfg(feature = "prover")]
+#[derive(Serialize, Deserialize, Tsify, Debug, Clone)]
+#[tsify(into_wasm_abi)]
+pub struct GachaTreeOutput {
+    #[serde(rename = "merkleRoot")]
+    pub merkle_root_hex: String,
+    #[serde(rename = "treeHeight")]
+    pub tree_height: usize,
+    /// `items[i]` is the contents of `item_{i}.json`
+    pub items: Vec<WasmMerkleLeafInputs>,
+}
+
+/// Collects leaves in the browser and builds the tree for the keys `params.bin` belongs to.
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+pub struct GachaTreeBuilder {
+    params: NativePoseidonConfig,
+    tree_height: usize,
+    leaves: Vec<GachaLeaf>,
+}
+
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+impl GachaTreeBuilder {
+    /// Reads the Poseidon parameters and tree height from `params.bin`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(params_bytes: &[u8]) -> Result<GachaTreeBuilder, JsValue> {
+        let (header, params): (_, NativePoseidonConfig) = decode_key_file(
+            KeyFileKind::PoseidonParams,
+            params_bytes,
+            crate::bindings::deserialization_policy(),
+        )?;
+        if poseidon_params_hash(&params)? != header.poseidon_params_hash {
+            return Err(GachaCircuitError::InvalidKeyFile(
+                "Poseidon parameters do not match the recorded hash".to_string(),
+            )
+            .into());
+        }
+        let tree_height = header.tree_height as usize;
+        if tree_height == 0 || tree_height > MAX_TREE_HEIGHT {
+            return Err(GachaCircuitError::InvalidKeyFile(format!(
+                "Tree height {} is not supported by the tree builder",
+                tree_height
+            ))
+            .into());
+        }
+        Ok(Self {
+            params,
+            tree_height,
+            leaves: Vec::new(),
+        })
+    }
+
+    /// Adds a leaf from a hex secret key and item id and returns its index.
+    #[wasm_bindgen(js_name = addLeaf)]
+    pub fn add_leaf(&mut self, secret_key_hex: &str, item_id_hex: &str) -> Result<usize, JsValue> {
+        let leaf = GachaLeaf {
+            secret_key: fr_decode(secret_key_hex, FieldEncoding::HexLe)
+                .map_err(|e| GachaCircuitError::invalid_field("secretKeyHex", e))?,
+            item_id: fr_decode(item_id_hex, FieldEncoding::HexLe)
+                .map_err(|e| GachaCircuitError::invalid_field("itemIdHex", e))?,
+        };
+        Ok(self.push(leaf)?)
+    }
+
+    /// Adds a leaf with a fresh random secret key and item id and returns its index.
+    #[wasm_bindgen(js_name = addRandomLeaf)]
+    pub fn add_random_leaf(&mut self) -> Result<usize, JsValue> {
+        let mut rng = get_rng(None)
+            .map_err(|e| GachaCircuitError::SetupError(format!("Failed to get RNG: {}", e)))?;
+        Ok(self.push(GachaLeaf::random(&mut rng))?)
+    }
+
+    /// Number of leaves added so far.
+    #[wasm_bindgen(getter, js_name = leafCount)]
+    pub fn leaf_count(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// The tree has `2^treeHeight` leaves.
+    #[wasm_bindgen(getter, js_name = treeHeight)]
+    pub fn tree_height(&self) -> usize {
+        self.tree_height
+    }
+
+    /// Builds the tree, filling the positions after the added leaves with random pairs.
+    pub fn build(&self) -> Result<GachaTreeOutput, JsValue> {
+        let mut rng = get_rng(None)
+            .map_err(|e| GachaCircuitError::SetupError(format!("Failed to get RNG: {}", e)))?;
+        let tree = GachaTree::build(
+            &self.params,
+            self.tree_height,
+            self.leaves.clone(),
+            &mut rng,
+        )?;
+        let items = (0..self.leaves.len())
+            .map(|index| tree.item_file(index))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(GachaTreeOutput {
+            merkle_root_hex: fr_to_hex(&tree.root())?,
+            tree_height: self.tree_height,
+            items,
+        })
+    }
+}
+
+#[cfg(feature = "prover")]
+impl GachaTreeBuilder {
+    fn push(&mut self, leaf: GachaLeaf) -> Result<usize, GachaCircuitError> {
+        if self.leaves.len() >= 1 << self.tree_height {
+            return Err(GachaCircuitError::InvalidInput(format!(
+                "The tree of height {} is full",
+                self.tree_height
+            )));
+        }
+        self.leaves.push(leaf);
+        Ok(self.leaves.len() - 1)
+    }
+}
+
+/// Progress reporting for JS callers. Before each [`ProvePhase`], `on_progress` is called as
+/// `onProgress(phase, step, totalSteps)` (e.g. `("msm_a", 2, 6)`) and then `cancel_token` is
+/// checked: once its `cancelled` property is truthy, proving stops with a cancellation error.
+/// Any object works as a token, e.g. `{ cancelled: false }` set from the callback, or one whose
+/// `cancelled` getter reads a `SharedArrayBuffer` written by another thread.
+#[cfg(feature = "prover")]
+pub(crate) struct JsProgress {
+    pub on_progress: Option<Function>,
+    pub cancel_token: JsValue,
+}
+
+#[cfg(feature = "prover")]
+impl JsProgress {
+    fn report(&self, phase: ProvePhase) -> Result<(), GachaCircuitError> {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress
+                .call3(
+                    &JsValue::NULL,
+                    &JsValue::from_str(phase.name()),
+                    &JsValue::from(phase.index() as u32),
+                    &JsValue::from(ProvePhase::ALL.len() as u32),
+                )
+                .map_err(|e| {
+                    GachaCircuitError::ProofGeneration(format!("Progress callback failed: {:?}", e))
+                })?;
+        }
+        let cancelled = !self.cancel_token.is_undefined()
+            && !self.cancel_token.is_null()
+            && Reflect::get(&self.cancel_token, &JsValue::from_str("cancelled"))
+                .is_ok_and(|cancelled| cancelled.is_truthy());
+        if cancelled {
+            return Err(GachaCircuitError::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+/// How the proving helpers below encode the proof they return.
+#[cfg(feature = "prover")]
+#[derive(Clone, Copy)]
+pub(crate) enum ProofEncoding<'a> {
+    /// The compressed Groth16 proof alone
+    Raw,
+    /// A binary [`GachaProofEnvelope`] labelled with `pool_id`
+    Envelope { pool_id: &'a str },
+}
+
+/// Proves inputs received from JS and serializes the proof.
+#[cfg(feature = "prover")]
+pub(crate) fn prove_wasm_inputs(
+    prover: &native::GachaProver,
+    wasm_inputs: WasmGachaCircuitInputs,
+    progress: Option<&JsProgress>,
+    encoding: ProofEncoding,
+) -> Result<Vec<u8>, GachaCircuitError> {
+    let report = |phase| progress.map_or(Ok(()), |progress| progress.report(phase));
+
+    // 1. Convert WASM inputs to Native inputs
+    let native_inputs = NativeGachaCircuitInputs::try_from(wasm_inputs)?;
+    let merkle_root = native_inputs.merkle_root;
+
+    // 2. Generate the proof
+    let proof = prover.prove_with_progress(&native_inputs, report)?;
+
+    // 3. Serialize proof
+    report(ProvePhase::Serialization)?;
+    match encoding {
+        ProofEncoding::Raw => {
+            let mut proof_bytes = Vec::new();
+            proof.serialize_compressed(&mut proof_bytes).map_err(|e| {
+                GachaCircuitError::Serialization(format!("Proof serialization: {}", e))
+            })?;
+            Ok(proof_bytes)
+        }
+        ProofEncoding::Envelope { pool_id } => {
+            let vk_fingerprint = *prover.pool().vk_fingerprint();
+            GachaProofEnvelope::new_user_pull(pool_id, vk_fingerprint, merkle_root, proof)
+                .to_bytes()
+        }
+    }
+}
+
+/// Decodes a hex root and proof bytes and verifies them.
+pub(crate) fn verify_js(
+    verifier: &native::GachaVerifier,
+    merkle_root_hex: &str,
+    proof_bytes: &[u8],
+) -> Result<bool, GachaCircuitError> {
+    let merkle_root = fr_decode(merkle_root_hex, FieldEncoding::HexLe)
+        .map_err(|e| GachaCircuitError::invalid_field("merkleRoot", e))?;
+    let proof: Proof<Bls12_381> = deserialize_compressed(
+        "Proof",
+        proof_bytes,
+        ValueKind::CurvePoints,
+        crate::bindings::deserialization_policy(),
+    )?;
+    verifier.verify(merkle_root, &proof)
+}
+
+/// Batch verification from JS arrays of hex roots and proof bytes.
+/// Proofs that cannot be deserialized are reported as invalid.
+pub(crate) fn verify_batch_js(
+    verifier: &native::GachaVerifier,
+    merkle_roots_js: JsValue,
+    proofs_js: JsValue,
+) -> Result<BatchVerificationResult, GachaCircuitError> {
+    // 1. Deserialize roots and proof bytes from JS
+    let merkle_roots_hex: Vec<String> = from_value(merkle_roots_js)
+        .map_err(|e| GachaCircuitError::Deserialization(format!("Merkle roots: {}", e)))?;
+    let proofs_bytes: Vec<Vec<u8>> = from_value(proofs_js)
+        .map_err(|e| GachaCircuitError::Deserialization(format!("Proofs: {}", e)))?;
+    if merkle_roots_hex.len() != proofs_bytes.len() {
+        return Err(GachaCircuitError::InvalidInput(format!(
+            "Batch length mismatch: {} roots but {} proofs",
+            merkle_roots_hex.len(),
+            proofs_bytes.len()
+        )));
+    }
+
+    // 2. Split off undecodable proofs; they count as invalid without entering the batch
+    let mut invalid_indices = Vec::new();
+    let mut batch_indices = Vec::new();
+    let mut merkle_roots = Vec::new();
+    let mut proofs = Vec::new();
+    let policy = crate::bindings::deserialization_policy();
+    for (i, (root_hex, bytes)) in merkle_roots_hex.iter().zip(&proofs_bytes).enumerate() {
+        let merkle_root = fr_decode(root_hex, FieldEncoding::HexLe)
+            .map_err(|e| GachaCircuitError::invalid_field(format!("merkleRoots[{}]", i), e))?;
+        match deserialize_compressed::<Proof<Bls12_381>>(
+            "Proof",
+            bytes,
+            ValueKind::CurvePoints,
+            policy,
+        ) {
+            Ok(proof) => {
+                batch_indices.push(i);
+                merkle_roots.push(merkle_root);
+                proofs.push(proof);
+            }
+            Err(_) => invalid_indices.push(i),
+        }
+    }
+
+    // 3. Batch verify the rest and map failures back to input positions
+    let batch_result = verifier.verify_batch(&merkle_roots, &proofs)?;
+    invalid_indices.extend(
+        batch_result
+            .invalid_indices
+            .iter()
+            .map(|&j| batch_indices[j]),
+    );
+    invalid_indices.sort_unstable();
+
+    Ok(BatchVerificationResult::from_invalid_indices(
+        invalid_indices,
+    ))
+}
+
+/// Poseidon hash of hex field elements with `pool`'s parameters, as the Merkle leaves are hashed.
+#[cfg(feature = "prover")]
+pub(crate) fn poseidon_hash_js(
+    pool: &GachaPool,
+    fields_hex: &[String],
+) -> Result<String, GachaCircuitError> {
+    let fields = fields_hex
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            fr_decode(field, FieldEncoding::HexLe)
+                .map_err(|e| GachaCircuitError::invalid_field(format!("fields[{}]", i), e))
+        })
+        .collect::<Result<Vec<ConstraintField>, _>>()?;
+    let digest = <PoseidonHash as MerkleHash>::LeafHash::evaluate(
+        pool.poseidon_params(),
+        fields.as_slice(),
+    )?;
+    fr_to_hex(&digest)
+}
+
+/// Digest of the Merkle leaf for a hex secret key and item id.
+#[cfg(feature = "prover")]
+pub(crate) fn leaf_digest_js(
+    pool: &GachaPool,
+    secret_key_hex: &str,
+    item_id_hex: &str,
+) -> Result<String, GachaCircuitError> {
+    let secret_key = fr_decode(secret_key_hex, FieldEncoding::HexLe)
+        .map_err(|e| GachaCircuitError::invalid_field("secretKeyHex", e))?;
+    let item_id = fr_decode(item_id_hex, FieldEncoding::HexLe)
+        .map_err(|e| GachaCircuitError::invalid_field("itemIdHex", e))?;
+    fr_to_hex(&leaf_digest::<PoseidonHash>(
+        pool.poseidon_params(),
+        secret_key,
+        item_id,
+    )?)
+}
+
+/// Checks an item file against a hex Merkle root without proving.
+#[cfg(feature = "prover")]
+pub(crate) fn verify_merkle_path_js(
+    pool: &GachaPool,
+    merkle_root_hex: String,
+    inputs: WasmMerkleLeafInputs,
+) -> Result<bool, GachaCircuitError> {
+    let native_inputs =
+        NativeGachaCircuitInputs::try_from(inputs.with_merkle_root(merkle_root_hex))?;
+    pool.verify_merkle_path(&native_inputs)
+}
@@ -0,0 +1,150 @@
+use ark_bls12_381::{Bls12_381, G1Affine, G1Projective};
+use ark_ec::{
+    pairing::{Pairing, PairingOutput},
+    CurveGroup, VariableBaseMSM,
+};
+use ark_ff::{Field, PrimeField, Zero};
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof};
+use ark_std::{rand::RngCore, vec::Vec};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify::Tsify;
+
+use crate::error::GachaCircuitError;
+use crate::types::{prepare_groth16_public_inputs, ConstraintField};
+
+/// Outcome of a batch verification.
+/// `invalid_indices` lists the positions (in the input order) of every proof that failed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify), tsify(into_wasm_abi))]
+pub struct BatchVerificationResult {
+    pub valid: bool,
+    #[serde(rename = "invalidIndices")]
+    pub invalid_indices: Vec<usize>,
+}
+
+impl BatchVerificationResult {
+    /// Builds the result from the indices that failed; the batch is valid iff none did.
+    pub fn from_invalid_indices(invalid_indices: Vec<usize>) -> Self {
+        Self {
+            valid: invalid_indices.is_empty(),
+            invalid_indices,
+        }
+    }
+}
+
+/// Verifies many `UserPullCircuit` proofs at once, `proofs[i]` against `merkle_roots[i]`.
+///
+/// Every Groth16 equation `e(A_i, B_i) = e(alpha, beta) * e(IC(x_i), gamma) * e(C_i, delta)` is
+/// raised to an independent random 128-bit scalar `r_i` and the results are multiplied together,
+/// so the whole batch costs one multi-Miller loop of `n + 2` pairs and a single final
+/// exponentiation. A forged proof survives the combination with probability at most 2^-128.
+///
+/// If the combined check fails, every proof is re-verified on its own to report which
+/// indices are invalid.
+pub fn verify_gacha_proofs_batch<R: RngCore>(
+    pvk: &PreparedVerifyingKey<Bls12_381>,
+    merkle_roots: &[ConstraintField],
+    proofs: &[Proof<Bls12_381>],
+    rng: &mut R,
+) -> Result<BatchVerificationResult, GachaCircuitError> {
+    if merkle_roots.len() != proofs.len() {
+        return Err(GachaCircuitError::InvalidInput(format!(
+            "Batch length mismatch: {} roots but {} proofs",
+            merkle_roots.len(),
+            proofs.len()
+        )));
+    }
+    if proofs.is_empty() {
+        return Ok(BatchVerificationResult::from_invalid_indices(Vec::new()));
+    }
+
+    // 1. Sample the random linear combination coefficients
+    let scalars: Vec<ConstraintField> = (0..proofs.len())
+        .map(|_| {
+            let r = ((rng.next_u64() as u128) << 64) | rng.next_u64() as u128;
+            ConstraintField::from(r)
+        })
+        .collect();
+    let scalar_sum: ConstraintField = scalars.iter().sum();
+
+    // 2. Combine the public inputs: sum_i r_i * IC(x_i)
+    let combined_ic = combine_public_inputs(pvk, merkle_roots, &scalars)?;
+
+    // 3. Combine the C terms and scale each A term by its coefficient
+    let c_points: Vec<G1Affine> = proofs.iter().map(|p| p.c).collect();
+    let combined_c = msm(&c_points, &scalars)?;
+    let scaled_a = G1Projective::normalize_batch(
+        &proofs
+            .iter()
+            .zip(&scalars)
+            .map(|(p, r)| p.a * r)
+            .collect::<Vec<_>>(),
+    );
+
+    // 4. One multi-Miller loop over all pairs, then a shared final exponentiation
+    let mut g1_terms: Vec<<Bls12_381 as Pairing>::G1Prepared> =
+        scaled_a.into_iter().map(Into::into).collect();
+    let mut g2_terms: Vec<<Bls12_381 as Pairing>::G2Prepared> =
+        proofs.iter().map(|p| p.b.into()).collect();
+    g1_terms.push(combined_ic.into_affine().into());
+    g2_terms.push(pvk.gamma_g2_neg_pc.clone());
+    g1_terms.push(combined_c.into_affine().into());
+    g2_terms.push(pvk.delta_g2_neg_pc.clone());
+
+    let miller_output = Bls12_381::multi_miller_loop(g1_terms, g2_terms);
+    let batch_ok = match Bls12_381::final_exponentiation(miller_output) {
+        Some(PairingOutput(result)) => result == pvk.alpha_g1_beta_g2.pow(scalar_sum.into_bigint()),
+        None => false,
+    };
+    if batch_ok {
+        return Ok(BatchVerificationResult::from_invalid_indices(Vec::new()));
+    }
+
+    // 5. Fall back to individual checks to locate the offending proofs
+    let mut invalid_indices = Vec::new();
+    for (i, (root, proof)) in merkle_roots.iter().zip(proofs).enumerate() {
+        let public_inputs = prepare_groth16_public_inputs(*root);
+        let is_valid = Groth16::<Bls12_381>::verify_proof(pvk, proof, &public_inputs)
+            .map_err(|e| GachaCircuitError::ProofVerification(e.to_string()))?;
+        if !is_valid {
+            invalid_indices.push(i);
+        }
+    }
+    Ok(BatchVerificationResult::from_invalid_indices(
+        invalid_indices,
+    ))
+}
+
+/// Computes `sum_i r_i * IC(x_i)` for the roots `x_i` and coefficients `r_i` as
+/// `IC_0 * sum_i r_i + sum_j IC_j * (sum_i r_i * x_ij)`, i.e. a single MSM over the VK's IC points.
+pub(crate) fn combine_public_inputs(
+    pvk: &PreparedVerifyingKey<Bls12_381>,
+    merkle_roots: &[ConstraintField],
+    scalars: &[ConstraintField],
+) -> Result<G1Projective, GachaCircuitError> {
+    let gamma_abc = &pvk.vk.gamma_abc_g1;
+    let mut combined_inputs = vec![ConstraintField::zero(); gamma_abc.len()];
+    combined_inputs[0] = scalars.iter().sum();
+    for (root, r) in merkle_roots.iter().zip(scalars) {
+        let public_inputs = prepare_groth16_public_inputs(*root);
+        if public_inputs.len() + 1 != gamma_abc.len() {
+            return Err(GachaCircuitError::ProofVerification(
+                "Verifying key does not match the number of public inputs".to_string(),
+            ));
+        }
+        for (acc, x) in combined_inputs.iter_mut().skip(1).zip(public_inputs) {
+            *acc += *r * x;
+        }
+    }
+    msm(gamma_abc, &combined_inputs)
+}
+
+pub(crate) fn msm(
+    bases: &[G1Affine],
+    scalars: &[ConstraintField],
+) -> Result<G1Projective, GachaCircuitError> {
+    G1Projective::msm(bases, scalars).map_err(|len| {
+        GachaCircuitError::ProofVerification(format!("MSM length mismatch ({})", len))
+    })
+}
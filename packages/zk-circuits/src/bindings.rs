@@ -0,0 +1,333 @@
+//! The wasm-bindgen free functions, behind the `wasm` feature.
+//!
+//! Keys live in a global [`PoolRegistry`]; every function looks its pool up per call, so pools
+//! can be loaded, replaced and unloaded at any time. The classes in [`crate::api`] own their keys
+//! instead. Everything here is a thin JS conversion layer over [`crate::native`] and [`GachaPool`].
+
+use parking_lot::Mutex;
+use wasm_bindgen::prelude::*;
+use web_sys::console; // Import console for logging
+
+use crate::api;
+#[cfg(feature = "prover")]
+use crate::api::ProofEncoding;
+use crate::batch::BatchVerificationResult;
+use crate::envelope::GachaProofEnvelope;
+use crate::error::GachaCircuitError;
+#[cfg(feature = "prover")]
+use crate::native::GachaProver;
+use crate::native::GachaVerifier;
+use crate::pool::{GachaPool, PoolRegistry, DEFAULT_POOL_ID};
+#[cfg(feature = "prover")]
+use crate::types::{WasmGachaCircuitInputs, WasmMerkleLeafInputs};
+use crate::utils::serialization::DeserializationPolicy;
+
+// --- Global Static Variables ---
+// Pools are looked up per call, so pools can be loaded, replaced and unloaded at any time
+static POOLS: Mutex<PoolRegistry> = Mutex::new(PoolRegistry::new());
+static DESERIALIZATION_POLICY: Mutex<DeserializationPolicy> =
+    Mutex::new(DeserializationPolicy::Checked);
+
+/// The policy set by `set_checked_deserialization`.
+pub(crate) fn deserialization_policy() -> DeserializationPolicy {
+    *DESERIALIZATION_POLICY.lock()
+}
+
+/// Sets up a panic hook
+#[wasm_bindgen(start)]
+pub fn main_js() -> Result<(), JsValue> {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+    Ok(())
+}
+
+/// Sets how keys and proofs passed to this module are validated when deserialized.
+/// `true` (the default) checks that every point is on the curve and in the prime-order subgroup;
+/// `false` skips those checks and should only be used for trusted, locally served files.
+#[wasm_bindgen]
+pub fn set_checked_deserialization(checked: bool) {
+    *DESERIALIZATION_POLICY.lock() = if checked {
+        DeserializationPolicy::Checked
+    } else {
+        DeserializationPolicy::Unchecked
+    };
+}
+
+/// Initializes keys and parameters (Type signatures updated)
+/// Expects the key files written by `generate_crs`; see [`crate::keys`] for the format and checks.
+/// Loads them as the `"default"` pool, which the functions without a pool id use.
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+pub fn init_gacha_keys(
+    pk_bytes: &[u8],
+    vk_bytes: &[u8],
+    params_bytes: &[u8],
+) -> Result<(), JsValue> {
+    if POOLS.lock().contains(DEFAULT_POOL_ID) {
+        return Err(GachaCircuitError::SetupError("Already initialized".to_string()).into());
+    }
+    load_pool(
+        DEFAULT_POOL_ID,
+        Some(pk_bytes.to_vec()),
+        vk_bytes,
+        params_bytes,
+    )?;
+
+    console::log_1(&"Gacha keys and params initialized successfully!".into());
+    Ok(())
+}
+
+/// Initializes verification only, from `gacha_vk.bin` and `params.bin`.
+/// This is the entry point of the verifier-only build; use `init_gacha_keys` to also prove.
+#[wasm_bindgen]
+pub fn init_verifier(vk_bytes: &[u8], params_bytes: &[u8]) -> Result<(), JsValue> {
+    if POOLS.lock().contains(DEFAULT_POOL_ID) {
+        return Err(GachaCircuitError::SetupError("Already initialized".to_string()).into());
+    }
+    load_pool(DEFAULT_POOL_ID, None, vk_bytes, params_bytes)?;
+
+    console::log_1(&"Gacha verifier initialized successfully!".into());
+    Ok(())
+}
+
+fn decode_pool(
+    pk_bytes: Option<Vec<u8>>,
+    vk_bytes: &[u8],
+    params_bytes: &[u8],
+) -> Result<GachaPool, GachaCircuitError> {
+    GachaPool::decode(
+        pk_bytes.as_deref(),
+        vk_bytes,
+        params_bytes,
+        deserialization_policy(),
+    )
+}
+
+/// Loads a pool from its key files under `pool_id`, which must not be loaded yet.
+/// Without `pk_bytes` the pool can only verify; the verifier-only build requires that.
+#[wasm_bindgen]
+pub fn load_pool(
+    pool_id: &str,
+    pk_bytes: Option<Vec<u8>>,
+    vk_bytes: &[u8],
+    params_bytes: &[u8],
+) -> Result<(), JsValue> {
+    let pool = decode_pool(pk_bytes, vk_bytes, params_bytes)?;
+    POOLS.lock().load(pool_id, pool)?;
+    Ok(())
+}
+
+/// Loads a pool under `pool_id`, swapping out any pool already loaded there (e.g. a new season).
+/// Proofs already running against the old keys finish with them. Returns whether a pool was replaced.
+#[wasm_bindgen]
+pub fn replace_pool(
+    pool_id: &str,
+    pk_bytes: Option<Vec<u8>>,
+    vk_bytes: &[u8],
+    params_bytes: &[u8],
+) -> Result<bool, JsValue> {
+    let pool = decode_pool(pk_bytes, vk_bytes, params_bytes)?;
+    Ok(POOLS.lock().replace(pool_id, pool).is_some())
+}
+
+/// Unloads a pool and frees its keys once no proof is using them. Returns whether it was loaded.
+#[wasm_bindgen]
+pub fn unload_pool(pool_id: &str) -> bool {
+    POOLS.lock().unload(pool_id).is_some()
+}
+
+/// Returns the ids of the loaded pools.
+#[wasm_bindgen]
+pub fn loaded_pools() -> Vec<String> {
+    POOLS.lock().pool_ids()
+}
+
+/// Generates a proof with the `"default"` pool.
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+pub fn generate_gacha_proof(inputs: WasmGachaCircuitInputs) -> Result<Vec<u8>, JsValue> {
+    generate_pool_proof(DEFAULT_POOL_ID, inputs)
+}
+
+/// Generates a proof with the keys of pool `pool_id`.
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+pub fn generate_pool_proof(
+    pool_id: &str,
+    inputs: WasmGachaCircuitInputs,
+) -> Result<Vec<u8>, JsValue> {
+    // The registry lock is not held while proving
+    let prover = GachaProver::from_pool(POOLS.lock().get(pool_id)?)?;
+    Ok(api::prove_wasm_inputs(
+        &prover,
+        inputs,
+        None,
+        ProofEncoding::Raw,
+    )?)
+}
+
+/// Generates a proof with pool `pool_id` and returns it as a binary `GachaProofEnvelope`,
+/// which carries the Merkle root, pool id and verifying key fingerprint along with the proof.
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+pub fn generate_proof_envelope(
+    pool_id: &str,
+    inputs: WasmGachaCircuitInputs,
+) -> Result<Vec<u8>, JsValue> {
+    let prover = GachaProver::from_pool(POOLS.lock().get(pool_id)?)?;
+    Ok(api::prove_wasm_inputs(
+        &prover,
+        inputs,
+        None,
+        ProofEncoding::Envelope { pool_id },
+    )?)
+}
+
+/// [`generate_gacha_proof`] with progress reporting and cancellation.
+/// `on_progress(phase, step, totalSteps)` is called before each phase (`synthesis`,
+/// `witness_map`, `msm_a`, `msm_b`, `msm_c`, `serialization`), after which proving stops if
+/// `cancel_token.cancelled` is truthy. Both are optional.
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+pub fn generate_gacha_proof_with_progress(
+    inputs: WasmGachaCircuitInputs,
+    #[wasm_bindgen(unchecked_param_type = "ProveProgressCallback | undefined")] on_progress: Option<
+        js_sys::Function,
+    >,
+    #[wasm_bindgen(unchecked_param_type = "CancellationToken | undefined")] cancel_token: JsValue,
+) -> Result<Vec<u8>, JsValue> {
+    generate_pool_proof_with_progress(DEFAULT_POOL_ID, inputs, on_progress, cancel_token)
+}
+
+/// [`generate_gacha_proof_with_progress`] with the keys of pool `pool_id`.
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+pub fn generate_pool_proof_with_progress(
+    pool_id: &str,
+    inputs: WasmGachaCircuitInputs,
+    #[wasm_bindgen(unchecked_param_type = "ProveProgressCallback | undefined")] on_progress: Option<
+        js_sys::Function,
+    >,
+    #[wasm_bindgen(unchecked_param_type = "CancellationToken | undefined")] cancel_token: JsValue,
+) -> Result<Vec<u8>, JsValue> {
+    let prover = GachaProver::from_pool(POOLS.lock().get(pool_id)?)?;
+    let progress = api::JsProgress {
+        on_progress,
+        cancel_token,
+    };
+    Ok(api::prove_wasm_inputs(
+        &prover,
+        inputs,
+        Some(&progress),
+        ProofEncoding::Raw,
+    )?)
+}
+
+/// Verifies a proof with the `"default"` pool.
+#[wasm_bindgen]
+pub fn verify_gacha_proof(merkle_root_hex: String, proof_bytes: &[u8]) -> Result<bool, JsValue> {
+    verify_pool_proof(DEFAULT_POOL_ID, merkle_root_hex, proof_bytes)
+}
+
+/// Verifies a proof with the verifying key of pool `pool_id`.
+#[wasm_bindgen]
+pub fn verify_pool_proof(
+    pool_id: &str,
+    merkle_root_hex: String,
+    proof_bytes: &[u8],
+) -> Result<bool, JsValue> {
+    let verifier = GachaVerifier::from_pool(POOLS.lock().get(pool_id)?);
+    Ok(api::verify_js(&verifier, &merkle_root_hex, proof_bytes)?)
+}
+
+/// Verifies a binary proof envelope with the pool named in it. Envelopes for another circuit,
+/// or made with keys other than the ones now loaded for that pool, are rejected with an error.
+#[wasm_bindgen]
+pub fn verify_proof_envelope(envelope_bytes: &[u8]) -> Result<bool, JsValue> {
+    let envelope = GachaProofEnvelope::from_bytes(envelope_bytes, deserialization_policy())?;
+    let verifier = GachaVerifier::from_pool(POOLS.lock().get(&envelope.pool_id)?);
+    Ok(verifier.verify_envelope(&envelope)?)
+}
+
+/// [`verify_proof_envelope`] for the JSON encoding.
+#[wasm_bindgen]
+pub fn verify_proof_envelope_json(envelope_json: &str) -> Result<bool, JsValue> {
+    verify_proof_envelope(&proof_envelope_from_json(envelope_json)?)
+}
+
+/// Converts a binary proof envelope to its JSON encoding.
+#[wasm_bindgen]
+pub fn proof_envelope_to_json(envelope_bytes: &[u8]) -> Result<String, JsValue> {
+    let envelope = GachaProofEnvelope::from_bytes(envelope_bytes, deserialization_policy())?;
+    Ok(envelope.to_json()?)
+}
+
+/// Converts a JSON proof envelope to its binary encoding.
+#[wasm_bindgen]
+pub fn proof_envelope_from_json(envelope_json: &str) -> Result<Vec<u8>, JsValue> {
+    let envelope = GachaProofEnvelope::from_json(envelope_json, deserialization_policy())?;
+    Ok(envelope.to_bytes()?)
+}
+
+/// Verifies many proofs in one batch with the `"default"` pool, `proofs[i]` against `merkleRoots[i]`.
+/// Takes a JS array of hex roots and a JS array of proof byte arrays, and returns
+/// `{ valid, invalidIndices }`. Proofs that cannot be deserialized are reported as invalid.
+#[wasm_bindgen]
+pub fn verify_gacha_proofs_batch(
+    #[wasm_bindgen(unchecked_param_type = "string[]")] merkle_roots_js: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] proofs_js: JsValue,
+) -> Result<BatchVerificationResult, JsValue> {
+    verify_pool_proofs_batch(DEFAULT_POOL_ID, merkle_roots_js, proofs_js)
+}
+
+/// [`verify_gacha_proofs_batch`] with the verifying key of pool `pool_id`.
+#[wasm_bindgen]
+pub fn verify_pool_proofs_batch(
+    pool_id: &str,
+    #[wasm_bindgen(unchecked_param_type = "string[]")] merkle_roots_js: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "Uint8Array[]")] proofs_js: JsValue,
+) -> Result<BatchVerificationResult, JsValue> {
+    let verifier = GachaVerifier::from_pool(POOLS.lock().get(pool_id)?);
+    Ok(api::verify_batch_js(&verifier, merkle_roots_js, proofs_js)?)
+}
+
+/// Poseidon hash of hex field elements (64 little-endian hex digits each) with the `"default"`
+/// pool's parameters, as the Merkle leaves are hashed. Returns the digest as hex.
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+pub fn poseidon_hash(fields: Vec<String>) -> Result<String, JsValue> {
+    let pool = POOLS.lock().get(DEFAULT_POOL_ID)?;
+    Ok(api::poseidon_hash_js(&pool, &fields)?)
+}
+
+/// Digest of the Merkle leaf `[secret, itemId]` with the `"default"` pool's parameters, as hex.
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+pub fn compute_leaf_digest(secret_key_hex: &str, item_id_hex: &str) -> Result<String, JsValue> {
+    let pool = POOLS.lock().get(DEFAULT_POOL_ID)?;
+    Ok(api::leaf_digest_js(&pool, secret_key_hex, item_id_hex)?)
+}
+
+/// Checks an item file (`item_{index}.json`) against a Merkle root natively, without proving,
+/// using the `"default"` pool. Returns whether its path leads to the root; a path of the wrong
+/// length for the pool's tree, or malformed field elements, are errors.
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+pub fn verify_merkle_path(
+    merkle_root_hex: String,
+    inputs: WasmMerkleLeafInputs,
+) -> Result<bool, JsValue> {
+    verify_pool_merkle_path(DEFAULT_POOL_ID, merkle_root_hex, inputs)
+}
+
+/// [`verify_merkle_path`] with the parameters and tree height of pool `pool_id`.
+#[cfg(feature = "prover")]
+#[wasm_bindgen]
+pub fn verify_pool_merkle_path(
+    pool_id: &str,
+    merkle_root_hex: String,
+    inputs: WasmMerkleLeafInputs,
+) -> Result<bool, JsValue> {
+    let pool = POOLS.lock().get(pool_id)?;
+    Ok(api::verify_merkle_path_js(&pool, merkle_root_hex, inputs)?)
+}
@@ -1,8 +1,8 @@
 use ark_crypto_primitives::{
     crh::{
         poseidon::{
-            constraints::{CRHGadget, TwoToOneCRHGadget}, // Import Poseidon gadgets
-            CRH as PoseidonCRH,                          // Native Poseidon 2-to-1 CRH
+            constraints::CRHGadget, // Poseidon gadget, for the parameter variable type
+            CRH as PoseidonCRH,     // Native Poseidon CRH
         },
         CRHSchemeGadget,
     },
@@ -17,19 +17,25 @@ use ark_r1cs_std::{
     prelude::*,
 };
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::marker::PhantomData;
 
 // Use types defined in types.rs
+use crate::hash::{MerkleHash, PoseidonHash};
+use crate::profiling::namespace;
+use crate::quaternary::{QuaternaryMerkleParams, QuaternaryMerklePath, QuaternaryMerklePathVar};
 use crate::types::{
-    ConstraintField, GachaMerkleConfig, NativeGachaCircuitInputs, NativeMerklePath,
-    NativePoseidonConfig,
+    ConstraintField, HashMerkleConfig, NativeGachaCircuitInputs, NativeMerklePathFor,
+    NativePoseidonConfig, QuaternaryGachaCircuitInputs,
 };
 
 // --- Merkle Tree Config Gadget ---
-// This configures the Merkle Tree operations *within the circuit*.
+// This configures the Merkle Tree operations *within the circuit* for a given hash backend.
 // It operates over the ConstraintField.
-struct GachaMerkleConfigGadget;
+pub struct HashMerkleConfigGadget<H: MerkleHash>(PhantomData<H>);
 
-impl ConfigGadget<GachaMerkleConfig, ConstraintField> for GachaMerkleConfigGadget {
+impl<H: MerkleHash> ConfigGadget<HashMerkleConfig<H>, ConstraintField>
+    for HashMerkleConfigGadget<H>
+{
     // Leaf data within the circuit is a slice of FpVar<ConstraintField>
     type Leaf = [FpVar<ConstraintField>];
     // Digests within the circuit are FpVar<ConstraintField>
@@ -38,24 +44,24 @@ impl ConfigGadget<GachaMerkleConfig, ConstraintField> for GachaMerkleConfigGadge
     // Converter is identity since digests are already FpVar<ConstraintField>
     type LeafInnerConverter = IdentityDigestConverter<FpVar<ConstraintField>>;
     // Hash gadgets operate over ConstraintField
-    type LeafHash = CRHGadget<ConstraintField>; // Gadget for H(secret_key_var, item_id_var)
-    type TwoToOneHash = TwoToOneCRHGadget<ConstraintField>; // Gadget for H(left_hash_var, right_hash_var)
+    type LeafHash = H::LeafHashGadget; // Gadget for H(secret_key_var, item_id_var)
+    type TwoToOneHash = H::TwoToOneHashGadget; // Gadget for H(left_hash_var, right_hash_var)
 }
 
 // Type alias for the Merkle Path Variable based on our circuit config
-type GachaMerklePathVar =
-    MerklePathVar<GachaMerkleConfig, ConstraintField, GachaMerkleConfigGadget>;
+type MerklePathVarFor<H> =
+    MerklePathVar<HashMerkleConfig<H>, ConstraintField, HashMerkleConfigGadget<H>>;
 
 // Type alias for the Poseidon Parameter Variable
-type PoseidonParametersVar = <CRHGadget<ConstraintField> as CRHSchemeGadget<
+pub(crate) type PoseidonParametersVar = <CRHGadget<ConstraintField> as CRHSchemeGadget<
     PoseidonCRH<ConstraintField>,
     ConstraintField,
 >>::ParametersVar;
 
 /// ZK-SNARK circuit for verifying a gacha pull.
-/// Defined over the ConstraintField (ark_bls12_381::Fr).
+/// Defined over the ConstraintField (ark_bls12_381::Fr), generic over the Merkle hash backend.
 #[derive(Clone)]
-pub struct UserPullCircuit {
+pub struct UserPullCircuit<H: MerkleHash = PoseidonHash> {
     // --- Public Inputs ---
     pub merkle_root: ConstraintField,
 
@@ -63,78 +69,174 @@ pub struct UserPullCircuit {
     pub item_id: ConstraintField,
     pub secret_key: ConstraintField,
     // The native Merkle path structure containing native field elements
-    pub native_merkle_path: NativeMerklePath,
+    pub native_merkle_path: NativeMerklePathFor<H>,
 
     // --- Parameters (Constants) ---
-    // Native Poseidon parameters for the ConstraintField
-    pub poseidon_params: NativePoseidonConfig,
+    // Native hash parameters for the ConstraintField
+    pub hash_params: H::Parameters,
 }
 
-// Implementation does not need generics C, GG anymore
-impl UserPullCircuit {
+impl<H: MerkleHash> UserPullCircuit<H> {
     /// Creates a new circuit instance from native inputs.
-    pub fn new(inputs: NativeGachaCircuitInputs, poseidon_params: NativePoseidonConfig) -> Self {
+    pub fn new(inputs: NativeGachaCircuitInputs<H>, hash_params: H::Parameters) -> Self {
         Self {
             merkle_root: inputs.merkle_root,
             item_id: inputs.item_id,
             secret_key: inputs.secret_key,
             native_merkle_path: inputs.native_merkle_path,
-            poseidon_params,
+            hash_params,
         }
     }
 }
 
 // Implement ConstraintSynthesizer for the ConstraintField (ark_bls12_381::Fr)
-impl ConstraintSynthesizer<ConstraintField> for UserPullCircuit {
+impl<H: MerkleHash> ConstraintSynthesizer<ConstraintField> for UserPullCircuit<H> {
     /// Generates the R1CS constraints for the circuit.
     fn generate_constraints(
         self,
         cs: ConstraintSystemRef<ConstraintField>,
     ) -> Result<(), SynthesisError> {
-        // 1. Allocate Public Input Variables
-        let merkle_root_var =
-            FpVar::<ConstraintField>::new_input(cs.clone(), || Ok(self.merkle_root))?;
-
-        // 2. Allocate Private Witness Variables
-        let item_id_var = FpVar::<ConstraintField>::new_witness(cs.clone(), || Ok(self.item_id))?;
-        let secret_key_var =
-            FpVar::<ConstraintField>::new_witness(cs.clone(), || Ok(self.secret_key))?;
-
-        // Allocate the Merkle path witness using the *native* path object.
-        // This internally allocates the necessary FpVar and Boolean variables.
-        let path_var = GachaMerklePathVar::new_witness(
-            ark_relations::ns!(cs, "merkle_path_witness"),
-            || Ok(self.native_merkle_path), // Provide the native path struct
+        let (merkle_root_var, item_id_var, secret_key_var) =
+            namespace(&cs, "allocate_inputs", || {
+                // 1. Allocate Public Input Variables
+                let merkle_root_var =
+                    FpVar::<ConstraintField>::new_input(cs.clone(), || Ok(self.merkle_root))?;
+
+                // 2. Allocate Private Witness Variables
+                let item_id_var =
+                    FpVar::<ConstraintField>::new_witness(cs.clone(), || Ok(self.item_id))?;
+                let secret_key_var =
+                    FpVar::<ConstraintField>::new_witness(cs.clone(), || Ok(self.secret_key))?;
+                Ok((merkle_root_var, item_id_var, secret_key_var))
+            })?;
+
+        // 3. Enforce membership of the leaf [secret_key, item_id] under the public root.
+        let leaf_data_vars = &[secret_key_var, item_id_var];
+        enforce_merkle_membership::<H>(
+            cs,
+            &merkle_root_var,
+            leaf_data_vars,
+            self.native_merkle_path,
+            &self.hash_params,
         )?;
 
-        // 3. Allocate Parameters as Constants
-        // Allocate the Poseidon parameters as circuit constants.
-        let params_var = PoseidonParametersVar::new_constant(cs.clone(), self.poseidon_params)?;
-        // Since LeafHash and TwoToOneHash use the same params in our config:
-        let leaf_params_var = &params_var;
-        let two_to_one_params_var = &params_var;
+        Ok(())
+    }
+}
 
-        // --- Define Constraints ---
+/// [`UserPullCircuit`] over a quaternary Merkle tree (see [`crate::quaternary`]).
+/// Same public input (the root) and leaf; the path has half as many levels.
+#[derive(Clone)]
+pub struct QuaternaryUserPullCircuit {
+    // --- Public Inputs ---
+    pub merkle_root: ConstraintField,
 
-        // Constraint 1: Verify the Merkle path membership.
-        // Define the leaf data *within the circuit* as required by GachaMerkleConfigGadget::Leaf
-        let leaf_data_vars = &[secret_key_var, item_id_var];
+    // --- Private Witness Inputs ---
+    pub item_id: ConstraintField,
+    pub secret_key: ConstraintField,
+    pub merkle_path: QuaternaryMerklePath,
+
+    // --- Parameters (Constants) ---
+    pub params: QuaternaryMerkleParams,
+}
+
+impl QuaternaryUserPullCircuit {
+    /// Creates a new circuit instance from native inputs.
+    pub fn new(inputs: QuaternaryGachaCircuitInputs, params: QuaternaryMerkleParams) -> Self {
+        Self {
+            merkle_root: inputs.merkle_root,
+            item_id: inputs.item_id,
+            secret_key: inputs.secret_key,
+            merkle_path: inputs.merkle_path,
+            params,
+        }
+    }
+}
 
-        // Call verify_membership on the allocated path variable.
-        // It will internally:
-        // - Use LeafHash (CRHGadget) with leaf_params_var to hash leaf_data_vars.
-        // - Use TwoToOneHash (TwoToOneCRHGadget) with two_to_one_params_var to hash inner nodes.
-        // - Compare the calculated root with merkle_root_var.
+impl ConstraintSynthesizer<ConstraintField> for QuaternaryUserPullCircuit {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintField>,
+    ) -> Result<(), SynthesisError> {
+        let (merkle_root_var, item_id_var, secret_key_var) =
+            namespace(&cs, "allocate_inputs", || {
+                let merkle_root_var =
+                    FpVar::<ConstraintField>::new_input(cs.clone(), || Ok(self.merkle_root))?;
+                let item_id_var =
+                    FpVar::<ConstraintField>::new_witness(cs.clone(), || Ok(self.item_id))?;
+                let secret_key_var =
+                    FpVar::<ConstraintField>::new_witness(cs.clone(), || Ok(self.secret_key))?;
+                Ok((merkle_root_var, item_id_var, secret_key_var))
+            })?;
+
+        let path_var = namespace(&cs, "merkle_path_witness", || {
+            QuaternaryMerklePathVar::new_witness(
+                ark_relations::ns!(cs, "quaternary_merkle_path_witness"),
+                || Ok(self.merkle_path),
+            )
+        })?;
+        let leaf_params_var = poseidon_params_var(cs.clone(), &self.params.leaf_params)?;
+        let node_params_var = poseidon_params_var(cs.clone(), &self.params.node_params)?;
+
+        namespace(&cs, "merkle_membership", || {
+            path_var
+                .verify_membership(
+                    &leaf_params_var,
+                    &node_params_var,
+                    &merkle_root_var,
+                    &[secret_key_var, item_id_var],
+                )?
+                .enforce_equal(&Boolean::TRUE)
+        })
+    }
+}
+
+/// Allocates `native_merkle_path` as a witness and enforces that `leaf_data_vars` is a leaf of
+/// the tree with root `merkle_root_var`. Shared by every circuit that proves a pull.
+pub(crate) fn enforce_merkle_membership<H: MerkleHash>(
+    cs: ConstraintSystemRef<ConstraintField>,
+    merkle_root_var: &FpVar<ConstraintField>,
+    leaf_data_vars: &[FpVar<ConstraintField>],
+    native_merkle_path: NativeMerklePathFor<H>,
+    hash_params: &H::Parameters,
+) -> Result<(), SynthesisError> {
+    // Allocate the Merkle path witness using the *native* path object.
+    // This internally allocates the necessary FpVar and Boolean variables.
+    let path_var = namespace(&cs, "merkle_path_witness", || {
+        MerklePathVarFor::<H>::new_witness(
+            ark_relations::ns!(cs, "merkle_path_witness"),
+            || Ok(native_merkle_path), // Provide the native path struct
+        )
+    })?;
+
+    // Allocate the hash parameters as circuit constants.
+    let params_var = H::ParametersVar::new_constant(cs.clone(), hash_params)?;
+    // Since LeafHash and TwoToOneHash use the same params in our config:
+    let leaf_params_var = &params_var;
+    let two_to_one_params_var = &params_var;
+
+    // Call verify_membership on the allocated path variable.
+    // It will internally:
+    // - Use LeafHash (H::LeafHashGadget) with leaf_params_var to hash leaf_data_vars.
+    // - Use TwoToOneHash (H::TwoToOneHashGadget) with two_to_one_params_var to hash inner nodes.
+    // - Compare the calculated root with merkle_root_var.
+    namespace(&cs, "merkle_membership", || {
         let membership_result = path_var.verify_membership(
             leaf_params_var,       // Params for Leaf Hash
             two_to_one_params_var, // Params for Inner Hash
-            &merkle_root_var,      // Public Root to verify against
+            merkle_root_var,       // Public Root to verify against
             leaf_data_vars,        // Leaf data *before* hashing
         )?;
 
         // Enforce that the membership proof verification succeeded.
-        membership_result.enforce_equal(&Boolean::TRUE)?;
+        membership_result.enforce_equal(&Boolean::TRUE)
+    })
+}
 
-        Ok(())
-    }
+/// Allocates the Poseidon parameters as circuit constants (no constraints are added).
+pub(crate) fn poseidon_params_var(
+    cs: ConstraintSystemRef<ConstraintField>,
+    poseidon_params: &NativePoseidonConfig,
+) -> Result<PoseidonParametersVar, SynthesisError> {
+    PoseidonParametersVar::new_constant(cs, poseidon_params)
 }
@@ -0,0 +1,315 @@
+//! Self-describing proof envelopes.
+//!
+//! A [`GachaProofEnvelope`] carries a Groth16 proof together with everything needed to check
+//! it: the public inputs, which circuit and pool it was made for, and the fingerprint of the
+//! verifying key, so a verifier never has to be told the Merkle root separately and an envelope
+//! made with other keys is rejected before any pairing is computed.
+//!
+//! The binary encoding is [`ENVELOPE_MAGIC`] followed by the compressed arkworks serialization
+//! of the fields in order:
+//!
+//! | field            | encoding                                             |
+//! |------------------|------------------------------------------------------|
+//! | `version`        | `u16`, [`ENVELOPE_VERSION`]                          |
+//! | `circuit_id`     | length-prefixed UTF-8, e.g. [`USER_PULL_CIRCUIT_ID`] |
+//! | `pool_id`        | length-prefixed UTF-8                                |
+//! | `vk_fingerprint` | 32 bytes, SHA-256 of the compressed verifying key    |
+//! | `public_inputs`  | length-prefixed scalar field elements                |
+//! | `proof`          | compressed Groth16 proof                             |
+//!
+//! Lengths are `u64` little-endian. Envelopes come from untrusted callers, so
+//! [`GachaProofEnvelope::from_bytes`] parses them by hand: ids longer than [`MAX_ID_LEN`] bytes,
+//! more than [`MAX_PUBLIC_INPUTS`] public inputs and trailing bytes are rejected before anything
+//! is allocated from a length prefix.
+//!
+//! The JSON encoding has the same fields in camelCase, with field elements as canonical `0x`
+//! little-endian hex (as elsewhere in the JS API) and the fingerprint and proof as plain hex.
+
+use ark_bls12_381::Bls12_381;
+use ark_groth16::Proof;
+use ark_serialize::CanonicalSerialize;
+use ark_std::{format, string::String, string::ToString, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+use crate::error::GachaCircuitError;
+use crate::types::{
+    fr_decode, fr_encode, prepare_groth16_public_inputs, ConstraintField, FieldEncoding,
+};
+use crate::utils::serialization::{deserialize_compressed, DeserializationPolicy, ValueKind};
+
+pub const ENVELOPE_MAGIC: [u8; 8] = *b"ZKGPROOF";
+pub const ENVELOPE_VERSION: u16 = 1;
+/// `UserPullCircuit` with the Poseidon Merkle hash, the circuit `generate_crs` sets up.
+pub const USER_PULL_CIRCUIT_ID: &str = "user-pull/poseidon";
+/// Longest `circuit_id` / `pool_id` accepted, in bytes.
+pub const MAX_ID_LEN: usize = 256;
+/// Most public inputs accepted; `UserPullCircuit` has one, the Merkle root.
+pub const MAX_PUBLIC_INPUTS: usize = 1;
+
+/// A proof with its public inputs and the identity of the keys it was made with.
+#[derive(CanonicalSerialize, Clone, Debug, PartialEq)]
+pub struct GachaProofEnvelope {
+    pub version: u16,
+    pub circuit_id: String,
+    pub pool_id: String,
+    pub vk_fingerprint: [u8; 32],
+    /// Public inputs in circuit order; for `UserPullCircuit` just the Merkle root
+    pub public_inputs: Vec<ConstraintField>,
+    pub proof: Proof<Bls12_381>,
+}
+
+/// JSON form of [`GachaProofEnvelope`].
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct GachaProofEnvelopeJson {
+    version: u16,
+    circuit_id: String,
+    pool_id: String,
+    vk_fingerprint: String,
+    public_inputs: Vec<String>,
+    proof: String,
+}
+
+impl GachaProofEnvelope {
+    /// Wraps a `UserPullCircuit` proof for the pull under `merkle_root`.
+    pub fn new_user_pull(
+        pool_id: &str,
+        vk_fingerprint: [u8; 32],
+        merkle_root: ConstraintField,
+        proof: Proof<Bls12_381>,
+    ) -> Self {
+        Self {
+            version: ENVELOPE_VERSION,
+            circuit_id: USER_PULL_CIRCUIT_ID.to_string(),
+            pool_id: pool_id.to_string(),
+            vk_fingerprint,
+            public_inputs: prepare_groth16_public_inputs(merkle_root),
+            proof,
+        }
+    }
+
+    /// The Merkle root the proof is for.
+    pub fn merkle_root(&self) -> Result<ConstraintField, GachaCircuitError> {
+        self.public_inputs.first().copied().ok_or_else(|| {
+            GachaCircuitError::InvalidEnvelope("Envelope has no public inputs".to_string())
+        })
+    }
+
+    /// Errors unless the envelope is for `UserPullCircuit` under a verifying key with
+    /// `vk_fingerprint`.
+    pub fn check_user_pull(&self, vk_fingerprint: &[u8; 32]) -> Result<(), GachaCircuitError> {
+        if self.circuit_id != USER_PULL_CIRCUIT_ID {
+            return Err(GachaCircuitError::InvalidEnvelope(format!(
+                "Envelope is for circuit '{}', expected '{}'",
+                self.circuit_id, USER_PULL_CIRCUIT_ID
+            )));
+        }
+        if &self.vk_fingerprint != vk_fingerprint {
+            return Err(GachaCircuitError::InvalidEnvelope(
+                "Envelope was made with a different verifying key".to_string(),
+            ));
+        }
+        let expected = prepare_groth16_public_inputs(ConstraintField::from(0u64)).len();
+        if self.public_inputs.len() != expected {
+            return Err(GachaCircuitError::InvalidEnvelope(format!(
+                "Envelope has {} public inputs, expected {}",
+                self.public_inputs.len(),
+                expected
+            )));
+        }
+        Ok(())
+    }
+
+    /// Errors if the ids or public inputs are longer than the parsers accept.
+    fn check_lengths(&self) -> Result<(), GachaCircuitError> {
+        check_id_len("circuit_id", self.circuit_id.len())?;
+        check_id_len("pool_id", self.pool_id.len())?;
+        check_public_inputs_len(self.public_inputs.len())
+    }
+
+    /// Binary encoding: [`ENVELOPE_MAGIC`] and the compressed fields.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, GachaCircuitError> {
+        self.check_lengths()?;
+        let mut bytes = Vec::with_capacity(ENVELOPE_MAGIC.len() + self.compressed_size());
+        bytes.extend_from_slice(&ENVELOPE_MAGIC);
+        self.serialize_compressed(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Parses the binary encoding; the proof's points are validated according to `policy`.
+    pub fn from_bytes(
+        bytes: &[u8],
+        policy: DeserializationPolicy,
+    ) -> Result<Self, GachaCircuitError> {
+        let payload = bytes.strip_prefix(&ENVELOPE_MAGIC[..]).ok_or_else(|| {
+            GachaCircuitError::InvalidEnvelope("Missing the proof envelope magic".to_string())
+        })?;
+        let mut reader = EnvelopeReader { bytes: payload };
+        // Check the version before parsing fields whose layout depends on it
+        let version = reader.read_u16()?;
+        check_version(version)?;
+        let circuit_id = reader.read_id("circuit_id")?;
+        let pool_id = reader.read_id("pool_id")?;
+        let vk_fingerprint = reader.take(32)?.try_into().expect("took 32 bytes");
+        let num_public_inputs = reader.read_len("public_inputs", MAX_PUBLIC_INPUTS)?;
+        let public_inputs = (0..num_public_inputs)
+            .map(|i| {
+                let bytes = reader.take(ConstraintField::from(0u64).compressed_size())?;
+                deserialize_compressed(
+                    &format!("Public input {}", i),
+                    bytes,
+                    ValueKind::FieldElements,
+                    policy,
+                )
+            })
+            .collect::<Result<Vec<ConstraintField>, _>>()?;
+        // The proof has a fixed size and ends the envelope
+        let proof_size = Proof::<Bls12_381>::default().compressed_size();
+        if reader.bytes.len() != proof_size {
+            return Err(GachaCircuitError::InvalidEnvelope(format!(
+                "Expected a {}-byte proof at the end of the envelope, found {} bytes",
+                proof_size,
+                reader.bytes.len()
+            )));
+        }
+        let proof = deserialize_compressed("Proof", reader.bytes, ValueKind::CurvePoints, policy)?;
+        Ok(Self {
+            version,
+            circuit_id,
+            pool_id,
+            vk_fingerprint,
+            public_inputs,
+            proof,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String, GachaCircuitError> {
+        self.check_lengths()?;
+        let mut proof = Vec::new();
+        self.proof.serialize_compressed(&mut proof)?;
+        let json = GachaProofEnvelopeJson {
+            version: self.version,
+            circuit_id: self.circuit_id.clone(),
+            pool_id: self.pool_id.clone(),
+            vk_fingerprint: hex::encode(self.vk_fingerprint),
+            public_inputs: self
+                .public_inputs
+                .iter()
+                .map(|input| fr_encode(input, FieldEncoding::HexLe))
+                .collect(),
+            proof: hex::encode(proof),
+        };
+        serde_json::to_string(&json)
+            .map_err(|e| GachaCircuitError::Serialization(format!("Proof envelope: {}", e)))
+    }
+
+    /// Parses the JSON encoding; the proof's points are validated according to `policy`.
+    pub fn from_json(json: &str, policy: DeserializationPolicy) -> Result<Self, GachaCircuitError> {
+        let json: GachaProofEnvelopeJson = serde_json::from_str(json)
+            .map_err(|e| GachaCircuitError::InvalidEnvelope(e.to_string()))?;
+        check_version(json.version)?;
+        check_id_len("circuit_id", json.circuit_id.len())?;
+        check_id_len("pool_id", json.pool_id.len())?;
+        check_public_inputs_len(json.public_inputs.len())?;
+        let vk_fingerprint = hex::decode(&json.vk_fingerprint)?.try_into().map_err(|_| {
+            GachaCircuitError::InvalidEnvelope("VK fingerprint must be 32 bytes".to_string())
+        })?;
+        let envelope = Self {
+            version: json.version,
+            circuit_id: json.circuit_id,
+            pool_id: json.pool_id,
+            vk_fingerprint,
+            public_inputs: json
+                .public_inputs
+                .iter()
+                .enumerate()
+                .map(|(i, input)| {
+                    fr_decode(input, FieldEncoding::HexLe).map_err(|e| {
+                        GachaCircuitError::invalid_field(format!("publicInputs[{}]", i), e)
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            proof: deserialize_compressed(
+                "Proof",
+                &hex::decode(&json.proof)?,
+                ValueKind::CurvePoints,
+                policy,
+            )?,
+        };
+        Ok(envelope)
+    }
+}
+
+fn check_version(version: u16) -> Result<(), GachaCircuitError> {
+    if version != ENVELOPE_VERSION {
+        return Err(GachaCircuitError::InvalidEnvelope(format!(
+            "Unsupported envelope version {} (expected {})",
+            version, ENVELOPE_VERSION
+        )));
+    }
+    Ok(())
+}
+
+fn check_id_len(field: &str, len: usize) -> Result<(), GachaCircuitError> {
+    if len > MAX_ID_LEN {
+        return Err(GachaCircuitError::InvalidEnvelope(format!(
+            "{} is {} bytes long, at most {} are allowed",
+            field, len, MAX_ID_LEN
+        )));
+    }
+    Ok(())
+}
+
+fn check_public_inputs_len(len: usize) -> Result<(), GachaCircuitError> {
+    if len > MAX_PUBLIC_INPUTS {
+        return Err(GachaCircuitError::InvalidEnvelope(format!(
+            "Envelope has {} public inputs, at most {} are allowed",
+            len, MAX_PUBLIC_INPUTS
+        )));
+    }
+    Ok(())
+}
+
+/// Reads the binary envelope fields, checking every length before using it.
+struct EnvelopeReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> EnvelopeReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], GachaCircuitError> {
+        if self.bytes.len() < len {
+            return Err(GachaCircuitError::InvalidEnvelope(
+                "Envelope is truncated".to_string(),
+            ));
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, GachaCircuitError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// A `u64` length prefix, rejected if above `max`.
+    fn read_len(&mut self, field: &str, max: usize) -> Result<usize, GachaCircuitError> {
+        let bytes: [u8; 8] = self.take(8)?.try_into().expect("took 8 bytes");
+        let len = u64::from_le_bytes(bytes);
+        if len > max as u64 {
+            return Err(GachaCircuitError::InvalidEnvelope(format!(
+                "{} has length {}, at most {} is allowed",
+                field, len, max
+            )));
+        }
+        Ok(len as usize)
+    }
+
+    fn read_id(&mut self, field: &str) -> Result<String, GachaCircuitError> {
+        let len = self.read_len(field, MAX_ID_LEN)?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| {
+            GachaCircuitError::InvalidEnvelope(format!("{} is not valid UTF-8", field))
+        })
+    }
+}
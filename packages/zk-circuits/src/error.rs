@@ -1,55 +1,200 @@
 // packages/zk-circuits/src/error.rs
-use ark_serialize::SerializationError;
 use ark_relations::r1cs::SynthesisError; // Ensure this is imported if using SynthesisError
-use thiserror::Error;
-use wasm_bindgen::JsValue;
-use ark_std::format; // Use ark_std's format macro
+use ark_serialize::SerializationError;
+use ark_std::{format, string::String, vec::Vec}; // Use ark_std's format macro
 use hex::FromHexError;
+#[cfg(feature = "wasm")]
+use js_sys::{Object, Reflect};
+use thiserror::Error;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
 
+/// Errors from every part of the crate.
+///
+/// In JS they are thrown as an `Error` named `"GachaCircuitError"` with a stable `code` (listed
+/// on each variant below), the variant name as `kind`, and for some variants a `details` object
+/// (see [`GachaCircuitError::details`]). Codes never change meaning; match on them instead of
+/// on the message.
 #[derive(Error, Debug)]
 pub enum GachaCircuitError {
+    /// Code `SERIALIZATION`
     // Keep using #[error] for message structure, but be careful with direct formatting of external types
     #[error("Serialization failed: {0}")]
     Serialization(String), // Use specific String message from source
 
+    /// Code `ARK_SERIALIZATION`; details: `cause`
     // Use a specific variant for ArkSerialization, avoid direct #[from] if Display is problematic
     #[error("Arkworks serialization failed")]
     ArkSerialization(SerializationError), // Store the original error
 
+    /// Code `DESERIALIZATION`
     #[error("Deserialization failed: {0}")]
     Deserialization(String),
 
+    /// Code `SYNTHESIS`; details: `cause`
     // Use a specific variant for Arkworks SynthesisError
     #[error("Arkworks constraint synthesis failed")]
     ArkSynthesis(SynthesisError), // Store the original error
 
+    /// Code `PROOF_GENERATION`
     #[error("Proof generation failed: {0}")]
     ProofGeneration(String),
 
+    /// Code `PROOF_VERIFICATION`
     #[error("Proof verification failed: {0}")]
     ProofVerification(String),
 
+    /// Code `NOT_INITIALIZED`
     #[error("System not initialized (Keys or Parameters missing)")]
     NotInitialized,
 
+    /// Code `INVALID_INPUT`
     #[error("Invalid input provided: {0}")]
     InvalidInput(String),
 
+    /// Code `INVALID_FIELD_ELEMENT`; details: `field`, the JS name of the input that failed to
+    /// decode (e.g. `merkleRoot` or `merklePathNodesHex[2]`)
+    #[error("Invalid field element in {field}: {reason}")]
+    InvalidFieldElement { field: String, reason: String },
+
+    /// Code `CONVERSION`
     #[error("Data conversion error: {0}")]
     ConversionError(String),
 
+    /// Code `HEX_DECODING`; details: `cause`
     // Use a specific variant for HexError
     #[error("Hex decoding failed")]
     HexDecoding(FromHexError), // #[from] might be okay here, but conversion below is safer
 
+    /// Code `SETUP`
     #[error("Setup error: {0}")]
     SetupError(String),
 
+    /// Code `INVALID_POINT`; details: `object`, what was being deserialized
+    #[error("Invalid curve point in {0}: not on the curve, not in the prime-order subgroup, or malformed")]
+    InvalidPoint(String),
+
+    /// Code `INVALID_KEY_FILE`
+    #[error("Invalid key file: {0}")]
+    InvalidKeyFile(String),
+
+    /// Code `INVALID_ENVELOPE`
+    #[error("Invalid proof envelope: {0}")]
+    InvalidEnvelope(String),
+
+    /// Code `POOL_NOT_FOUND`; details: `poolId`
+    #[error("Pool not loaded: {0}")]
+    PoolNotFound(String),
+
+    /// Code `CANCELLED`
+    #[error("Proof generation was cancelled")]
+    Cancelled,
+
+    /// Code `CRYPTO_PRIMITIVE`; details: `cause`
     // Use a specific variant for PrimitiveError
     #[error("Cryptographic primitive error")]
     Primitive(ark_crypto_primitives::Error), // Store the original error
 }
 
+impl GachaCircuitError {
+    /// Wraps an error from decoding the input named `field` (its JS name).
+    pub fn invalid_field(field: impl Into<String>, source: GachaCircuitError) -> Self {
+        let reason = match source {
+            GachaCircuitError::InvalidInput(message) => message,
+            GachaCircuitError::HexDecoding(err) => format!("{}", err),
+            other => format!("{}", other),
+        };
+        GachaCircuitError::InvalidFieldElement {
+            field: field.into(),
+            reason,
+        }
+    }
+
+    /// The stable code JS callers match on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            GachaCircuitError::Serialization(_) => "SERIALIZATION",
+            GachaCircuitError::ArkSerialization(_) => "ARK_SERIALIZATION",
+            GachaCircuitError::Deserialization(_) => "DESERIALIZATION",
+            GachaCircuitError::ArkSynthesis(_) => "SYNTHESIS",
+            GachaCircuitError::ProofGeneration(_) => "PROOF_GENERATION",
+            GachaCircuitError::ProofVerification(_) => "PROOF_VERIFICATION",
+            GachaCircuitError::NotInitialized => "NOT_INITIALIZED",
+            GachaCircuitError::InvalidInput(_) => "INVALID_INPUT",
+            GachaCircuitError::InvalidFieldElement { .. } => "INVALID_FIELD_ELEMENT",
+            GachaCircuitError::ConversionError(_) => "CONVERSION",
+            GachaCircuitError::HexDecoding(_) => "HEX_DECODING",
+            GachaCircuitError::SetupError(_) => "SETUP",
+            GachaCircuitError::InvalidPoint(_) => "INVALID_POINT",
+            GachaCircuitError::InvalidKeyFile(_) => "INVALID_KEY_FILE",
+            GachaCircuitError::InvalidEnvelope(_) => "INVALID_ENVELOPE",
+            GachaCircuitError::PoolNotFound(_) => "POOL_NOT_FOUND",
+            GachaCircuitError::Cancelled => "CANCELLED",
+            GachaCircuitError::Primitive(_) => "CRYPTO_PRIMITIVE",
+        }
+    }
+
+    /// The variant name, e.g. `"InvalidInput"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GachaCircuitError::Serialization(_) => "Serialization",
+            GachaCircuitError::ArkSerialization(_) => "ArkSerialization",
+            GachaCircuitError::Deserialization(_) => "Deserialization",
+            GachaCircuitError::ArkSynthesis(_) => "ArkSynthesis",
+            GachaCircuitError::ProofGeneration(_) => "ProofGeneration",
+            GachaCircuitError::ProofVerification(_) => "ProofVerification",
+            GachaCircuitError::NotInitialized => "NotInitialized",
+            GachaCircuitError::InvalidInput(_) => "InvalidInput",
+            GachaCircuitError::InvalidFieldElement { .. } => "InvalidFieldElement",
+            GachaCircuitError::ConversionError(_) => "ConversionError",
+            GachaCircuitError::HexDecoding(_) => "HexDecoding",
+            GachaCircuitError::SetupError(_) => "SetupError",
+            GachaCircuitError::InvalidPoint(_) => "InvalidPoint",
+            GachaCircuitError::InvalidKeyFile(_) => "InvalidKeyFile",
+            GachaCircuitError::InvalidEnvelope(_) => "InvalidEnvelope",
+            GachaCircuitError::PoolNotFound(_) => "PoolNotFound",
+            GachaCircuitError::Cancelled => "Cancelled",
+            GachaCircuitError::Primitive(_) => "Primitive",
+        }
+    }
+
+    /// Structured details for the variants documented to have them, as `(key, value)` pairs.
+    pub fn details(&self) -> Vec<(&'static str, String)> {
+        match self {
+            GachaCircuitError::ArkSerialization(err) => vec![("cause", format!("{:?}", err))],
+            GachaCircuitError::ArkSynthesis(err) => vec![("cause", format!("{:?}", err))],
+            GachaCircuitError::HexDecoding(err) => vec![("cause", format!("{}", err))],
+            GachaCircuitError::Primitive(err) => vec![("cause", format!("{:?}", err))],
+            GachaCircuitError::InvalidFieldElement { field, .. } => vec![("field", field.clone())],
+            GachaCircuitError::InvalidPoint(object) => vec![("object", object.clone())],
+            GachaCircuitError::PoolNotFound(pool_id) => vec![("poolId", pool_id.clone())],
+            _ => Vec::new(),
+        }
+    }
+
+    /// The human-readable message, with the details of wrapped arkworks and hex errors.
+    pub fn message(&self) -> String {
+        match self {
+            // For variants wrapping external errors, create a descriptive string
+            // Optionally include Debug formatting of the original error for more detail
+            GachaCircuitError::ArkSerialization(ref err) => {
+                format!("Error: Arkworks Serialization Failed (Details: {:?})", err)
+            }
+            GachaCircuitError::ArkSynthesis(ref err) => {
+                format!("Error: Arkworks Synthesis Failed (Details: {:?})", err)
+            }
+            GachaCircuitError::HexDecoding(ref err) => {
+                format!("Error: Hex Decoding Failed (Details: {:?})", err)
+            }
+            GachaCircuitError::Primitive(ref err) => {
+                format!("Error: Cryptographic Primitive Failed (Details: {:?})", err)
+            }
+            // For variants already containing Strings or simple messages, use them directly
+            _ => format!("{}", self), // Use the #[error("...")] message for others
+        }
+    }
+}
+
 // --- Custom From implementations to capture source ---
 
 impl From<SerializationError> for GachaCircuitError {
@@ -84,29 +229,47 @@ impl From<ark_crypto_primitives::Error> for GachaCircuitError {
     }
 }
 
+// --- Conversion to JsValue ---
 
-// --- Conversion to JsValue (More Controlled String Formatting) ---
+// Shape of the errors thrown to JS (see `From<GachaCircuitError> for JsValue`)
+#[cfg(feature = "wasm")]
+#[wasm_bindgen(typescript_custom_section)]
+const GACHA_CIRCUIT_ERROR_TS: &str = r#"
+/** Thrown by every zk_circuits function; match on `code`, which never changes meaning. */
+export interface GachaCircuitError extends Error {
+  name: "GachaCircuitError";
+  code: GachaErrorCode;
+  /** The Rust variant, e.g. "InvalidInput" */
+  kind: string;
+  /** e.g. `{ field: "merkleRoot" }` for INVALID_FIELD_ELEMENT, `{ poolId }` for POOL_NOT_FOUND */
+  details?: Record<string, string>;
+}
+export type GachaErrorCode =
+  | "SERIALIZATION" | "ARK_SERIALIZATION" | "DESERIALIZATION" | "SYNTHESIS"
+  | "PROOF_GENERATION" | "PROOF_VERIFICATION" | "NOT_INITIALIZED" | "INVALID_INPUT"
+  | "INVALID_FIELD_ELEMENT" | "CONVERSION" | "HEX_DECODING" | "SETUP" | "INVALID_POINT"
+  | "INVALID_KEY_FILE" | "INVALID_ENVELOPE" | "POOL_NOT_FOUND" | "CANCELLED" | "CRYPTO_PRIMITIVE";
+"#;
 
+#[cfg(feature = "wasm")]
 impl From<GachaCircuitError> for JsValue {
     fn from(error: GachaCircuitError) -> Self {
-        let error_message = match error {
-            // For variants wrapping external errors, create a descriptive string
-            // Optionally include Debug formatting of the original error for more detail
-            GachaCircuitError::ArkSerialization(ref err) => {
-                format!("Error: Arkworks Serialization Failed (Details: {:?})", err)
-            }
-            GachaCircuitError::ArkSynthesis(ref err) => {
-                format!("Error: Arkworks Synthesis Failed (Details: {:?})", err)
-            }
-             GachaCircuitError::HexDecoding(ref err) => {
-                format!("Error: Hex Decoding Failed (Details: {:?})", err)
-            }
-             GachaCircuitError::Primitive(ref err) => {
-                format!("Error: Cryptographic Primitive Failed (Details: {:?})", err)
-            }
-            // For variants already containing Strings or simple messages, use them directly
-            _ => format!("{}", error), // Use the #[error("...")] message for others
+        let js_error = js_sys::Error::new(&error.message());
+        js_error.set_name("GachaCircuitError");
+        // Setting properties on a fresh Error object cannot fail
+        let set = |target: &JsValue, key: &str, value: &JsValue| {
+            let _ = Reflect::set(target, &JsValue::from_str(key), value);
         };
-        JsValue::from_str(&error_message)
+        set(&js_error, "code", &JsValue::from_str(error.code()));
+        set(&js_error, "kind", &JsValue::from_str(error.kind()));
+        let details = error.details();
+        if !details.is_empty() {
+            let object = Object::new();
+            for (key, value) in details {
+                set(&object, key, &JsValue::from_str(&value));
+            }
+            set(&js_error, "details", &object);
+        }
+        js_error.into()
     }
-}
\ No newline at end of file
+}
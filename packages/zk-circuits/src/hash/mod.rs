@@ -0,0 +1,90 @@
+//! Hash backends for the gacha Merkle tree.
+//!
+//! [`MerkleHash`] bundles a native leaf hash, a native two-to-one hash and their gadgets, so the
+//! Merkle configs, `UserPullCircuit` and the tree builders are generic over the hash instead of
+//! hard-coding Poseidon. [`PoseidonHash`] is always available and is what deployed keys use;
+//! Poseidon2 and Rescue-Prime are behind the `poseidon2` and `rescue` features.
+
+use ark_crypto_primitives::crh::{
+    poseidon::{
+        constraints::{CRHGadget, CRHParametersVar, TwoToOneCRHGadget},
+        TwoToOneCRH, CRH,
+    },
+    CRHScheme, CRHSchemeGadget, TwoToOneCRHScheme, TwoToOneCRHSchemeGadget,
+};
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::fmt::Debug;
+
+use crate::types::{ConstraintField, NativePoseidonConfig};
+
+#[cfg(feature = "poseidon2")]
+pub mod poseidon2;
+#[cfg(feature = "rescue")]
+pub mod rescue;
+#[cfg(any(feature = "poseidon2", feature = "rescue"))]
+pub mod sponge;
+
+#[cfg(feature = "poseidon2")]
+pub use poseidon2::Poseidon2Hash;
+#[cfg(feature = "rescue")]
+pub use rescue::RescuePrimeHash;
+
+/// A field-native hash usable for both Merkle tree layers, natively and in-circuit.
+pub trait MerkleHash: Clone + Debug + Send + Sync + 'static {
+    /// Short name for reports and benchmarks.
+    const NAME: &'static str;
+
+    type Parameters: Clone + Debug + CanonicalSerialize + CanonicalDeserialize + Send + Sync;
+    type ParametersVar: AllocVar<Self::Parameters, ConstraintField> + Clone;
+
+    type LeafHash: CRHScheme<
+        Input = [ConstraintField],
+        Output = ConstraintField,
+        Parameters = Self::Parameters,
+    >;
+    type TwoToOneHash: TwoToOneCRHScheme<
+        Input = ConstraintField,
+        Output = ConstraintField,
+        Parameters = Self::Parameters,
+    >;
+
+    type LeafHashGadget: CRHSchemeGadget<
+        Self::LeafHash,
+        ConstraintField,
+        InputVar = [FpVar<ConstraintField>],
+        OutputVar = FpVar<ConstraintField>,
+        ParametersVar = Self::ParametersVar,
+    >;
+    type TwoToOneHashGadget: TwoToOneCRHSchemeGadget<
+        Self::TwoToOneHash,
+        ConstraintField,
+        InputVar = FpVar<ConstraintField>,
+        OutputVar = FpVar<ConstraintField>,
+        ParametersVar = Self::ParametersVar,
+    >;
+
+    /// Parameters at the crate's default (128-bit) security level.
+    fn default_parameters() -> Self::Parameters;
+}
+
+/// Poseidon via arkworks' sponge, with parameters from [`crate::poseidon`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PoseidonHash;
+
+impl MerkleHash for PoseidonHash {
+    const NAME: &'static str = "poseidon";
+
+    type Parameters = NativePoseidonConfig;
+    type ParametersVar = CRHParametersVar<ConstraintField>;
+
+    type LeafHash = CRH<ConstraintField>;
+    type TwoToOneHash = TwoToOneCRH<ConstraintField>;
+
+    type LeafHashGadget = CRHGadget<ConstraintField>;
+    type TwoToOneHashGadget = TwoToOneCRHGadget<ConstraintField>;
+
+    fn default_parameters() -> Self::Parameters {
+        crate::poseidon::default_poseidon_config()
+    }
+}
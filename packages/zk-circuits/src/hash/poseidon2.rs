@@ -0,0 +1,185 @@
+//! Poseidon2 (Grassi, Khovratovich, Schofnegger, ePrint 2023/323) for width 3.
+//!
+//! External rounds use the matrix `circ(2, 1, 1)` and internal rounds `1 + diag(1, 1, 2)`, as in
+//! the paper's `t = 3` instance. Round numbers come from the Poseidon derivation in
+//! [`crate::poseidon`]; round constants are derived with SHA-512, so digests are not
+//! interoperable with other Poseidon2 instantiations.
+
+use ark_ff::Field;
+use ark_r1cs_std::fields::{fp::FpVar, FieldVar};
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::vec::Vec;
+
+use super::sponge::{
+    derive_constants, Permutation, SpongeCRH, SpongeCRHGadget, SpongeParametersVar, WIDTH,
+};
+use super::MerkleHash;
+use crate::poseidon::{round_numbers, DEFAULT_SECURITY_LEVEL};
+use crate::types::ConstraintField;
+
+/// S-box exponent; the smallest permutation exponent of the BLS12-381 scalar field.
+pub const POSEIDON2_ALPHA: u64 = 5;
+
+const CONSTANTS_LABEL: &[u8] = b"zk-gacha-poseidon2-w3-v1";
+
+/// Round counts are the lengths of the constant lists, so every round has its constants and a
+/// deserialized parameter set cannot be short of them.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Poseidon2Parameters {
+    pub alpha: u64,
+    /// `WIDTH` constants per full round; the first half runs before the partial rounds
+    pub full_round_constants: Vec<[ConstraintField; WIDTH]>,
+    /// One constant per partial round
+    pub partial_round_constants: Vec<ConstraintField>,
+}
+
+impl Poseidon2Parameters {
+    pub fn new(security_level: u32) -> Self {
+        let rounds = round_numbers(security_level, WIDTH, POSEIDON2_ALPHA)
+            .expect("x^5 is a permutation of the scalar field");
+        let count = rounds.full_rounds * WIDTH + rounds.partial_rounds;
+        // Constants are derived in round order: half the full rounds, the partial rounds, the rest
+        let constants = derive_constants(CONSTANTS_LABEL, count);
+        let (first_full, rest) = constants.split_at(rounds.full_rounds / 2 * WIDTH);
+        let (partial, last_full) = rest.split_at(rounds.partial_rounds);
+        let full_round_constants = first_full
+            .chunks_exact(WIDTH)
+            .chain(last_full.chunks_exact(WIDTH))
+            .map(|chunk| chunk.try_into().expect("exact chunk"))
+            .collect();
+        Self {
+            alpha: POSEIDON2_ALPHA,
+            full_round_constants,
+            partial_round_constants: partial.to_vec(),
+        }
+    }
+
+    pub fn full_rounds(&self) -> usize {
+        self.full_round_constants.len()
+    }
+
+    pub fn partial_rounds(&self) -> usize {
+        self.partial_round_constants.len()
+    }
+
+    /// The full-round constants before and after the partial rounds.
+    fn full_round_halves(&self) -> (&[[ConstraintField; WIDTH]], &[[ConstraintField; WIDTH]]) {
+        self.full_round_constants.split_at(self.full_rounds() / 2)
+    }
+}
+
+/// The Poseidon2 permutation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Poseidon2Permutation;
+
+/// Poseidon2 Merkle hash backend.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Poseidon2Hash;
+
+impl MerkleHash for Poseidon2Hash {
+    const NAME: &'static str = "poseidon2";
+
+    type Parameters = Poseidon2Parameters;
+    type ParametersVar = SpongeParametersVar<Poseidon2Permutation>;
+
+    type LeafHash = SpongeCRH<Poseidon2Permutation>;
+    type TwoToOneHash = SpongeCRH<Poseidon2Permutation>;
+
+    type LeafHashGadget = SpongeCRHGadget<Poseidon2Permutation>;
+    type TwoToOneHashGadget = SpongeCRHGadget<Poseidon2Permutation>;
+
+    fn default_parameters() -> Self::Parameters {
+        Poseidon2Permutation::default_parameters()
+    }
+}
+
+impl Permutation for Poseidon2Permutation {
+    type Parameters = Poseidon2Parameters;
+
+    fn default_parameters() -> Self::Parameters {
+        Poseidon2Parameters::new(DEFAULT_SECURITY_LEVEL)
+    }
+
+    fn permute(params: &Self::Parameters, state: &mut [ConstraintField; WIDTH]) {
+        let (first_full, last_full) = params.full_round_halves();
+        let sbox = |x: ConstraintField| x.pow([params.alpha]);
+        let full_round = |state: &mut [ConstraintField; WIDTH],
+                          constants: &[ConstraintField; WIDTH]| {
+            for (cell, constant) in state.iter_mut().zip(constants) {
+                *cell = sbox(*cell + constant);
+            }
+            external_layer(state);
+        };
+
+        external_layer(state);
+        for constants in first_full {
+            full_round(state, constants);
+        }
+        for constant in &params.partial_round_constants {
+            state[0] = sbox(state[0] + constant);
+            internal_layer(state);
+        }
+        for constants in last_full {
+            full_round(state, constants);
+        }
+    }
+
+    fn permute_var(
+        params: &Self::Parameters,
+        state: &mut [FpVar<ConstraintField>; WIDTH],
+    ) -> Result<(), SynthesisError> {
+        let (first_full, last_full) = params.full_round_halves();
+        let sbox = |x: FpVar<ConstraintField>| x.pow_by_constant([params.alpha]);
+        let full_round = |state: &mut [FpVar<ConstraintField>; WIDTH],
+                          constants: &[ConstraintField; WIDTH]|
+         -> Result<(), SynthesisError> {
+            for (cell, constant) in state.iter_mut().zip(constants) {
+                *cell = sbox(&*cell + *constant)?;
+            }
+            external_layer(state);
+            Ok(())
+        };
+
+        external_layer(state);
+        for constants in first_full {
+            full_round(state, constants)?;
+        }
+        for constant in &params.partial_round_constants {
+            state[0] = sbox(&state[0] + *constant)?;
+            internal_layer(state);
+        }
+        for constants in last_full {
+            full_round(state, constants)?;
+        }
+        Ok(())
+    }
+}
+
+/// `circ(2, 1, 1)`: every cell gets the sum of the state added to it.
+fn external_layer<T>(state: &mut [T; WIDTH])
+where
+    T: Clone + for<'a> core::ops::AddAssign<&'a T>,
+{
+    let mut sum = state[0].clone();
+    sum += &state[1];
+    sum += &state[2];
+    for cell in state.iter_mut() {
+        *cell += &sum;
+    }
+}
+
+/// `1 + diag(1, 1, 2)`: the external layer with the last cell counted once more.
+fn internal_layer<T>(state: &mut [T; WIDTH])
+where
+    T: Clone + for<'a> core::ops::AddAssign<&'a T>,
+{
+    let mut sum = state[0].clone();
+    sum += &state[1];
+    sum += &state[2];
+    let last = state[2].clone();
+    for cell in state.iter_mut() {
+        *cell += &sum;
+    }
+    state[2] += &last;
+}
@@ -0,0 +1,226 @@
+//! Rescue-Prime (Szepieniec, Ashur, Dhooghe, ePrint 2020/1143) for width 3.
+//!
+//! Each round applies `x^alpha`, the MDS matrix and round constants, then `x^(1/alpha)`, the MDS
+//! matrix and round constants again. In-circuit, the inverse S-box is a witness `y` with
+//! `y^alpha = x` enforced, so both halves cost the same. The round count follows the spec's
+//! Gröbner-basis bound with its 50% margin. The MDS matrix is a Cauchy matrix and the round
+//! constants are derived with SHA-512, so digests are not interoperable with other instances.
+
+use ark_ff::{Field, PrimeField};
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    eq::EqGadget,
+    fields::{fp::FpVar, FieldVar},
+    R1CSVar,
+};
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::vec::Vec;
+
+use super::sponge::{
+    derive_constants, Permutation, SpongeCRH, SpongeCRHGadget, SpongeParametersVar, RATE, WIDTH,
+};
+use super::MerkleHash;
+use crate::poseidon::{log2_binomial, DEFAULT_SECURITY_LEVEL};
+use crate::types::ConstraintField;
+
+/// S-box exponent; the smallest permutation exponent of the BLS12-381 scalar field.
+pub const RESCUE_ALPHA: u64 = 5;
+
+const CONSTANTS_LABEL: &[u8] = b"zk-gacha-rescue-prime-w3-v1";
+
+/// The round count is the length of the constant list and the MDS matrix has a fixed shape, so a
+/// deserialized parameter set cannot be short of either.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct RescuePrimeParameters {
+    pub alpha: u64,
+    /// `alpha^-1 mod (p - 1)` as little-endian limbs
+    pub alpha_inv: Vec<u64>,
+    pub mds: [[ConstraintField; WIDTH]; WIDTH],
+    /// Per round, the constants added after the S-box and after the inverse S-box
+    pub round_constants: Vec<[[ConstraintField; WIDTH]; 2]>,
+}
+
+impl RescuePrimeParameters {
+    pub fn new(security_level: u32) -> Self {
+        let rounds = rescue_rounds(security_level, RESCUE_ALPHA);
+        // Cauchy matrix 1 / (x_i + y_j) with x_i = i, y_j = WIDTH + j: every minor is non-singular
+        let mds = core::array::from_fn(|i| {
+            core::array::from_fn(|j| {
+                ConstraintField::from((i + WIDTH + j) as u64)
+                    .inverse()
+                    .expect("non-zero")
+            })
+        });
+        let constants = derive_constants(CONSTANTS_LABEL, 2 * WIDTH * rounds);
+        let round_constants = constants
+            .chunks_exact(2 * WIDTH)
+            .map(|round| {
+                let (forward, backward) = round.split_at(WIDTH);
+                [
+                    forward.try_into().expect("exact chunk"),
+                    backward.try_into().expect("exact chunk"),
+                ]
+            })
+            .collect();
+        Self {
+            alpha: RESCUE_ALPHA,
+            alpha_inv: inverse_exponent(RESCUE_ALPHA)
+                .expect("x^5 is a permutation of the scalar field"),
+            mds,
+            round_constants,
+        }
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.round_constants.len()
+    }
+}
+
+/// The Rescue-Prime permutation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RescuePrimePermutation;
+
+/// Rescue-Prime Merkle hash backend.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RescuePrimeHash;
+
+impl MerkleHash for RescuePrimeHash {
+    const NAME: &'static str = "rescue-prime";
+
+    type Parameters = RescuePrimeParameters;
+    type ParametersVar = SpongeParametersVar<RescuePrimePermutation>;
+
+    type LeafHash = SpongeCRH<RescuePrimePermutation>;
+    type TwoToOneHash = SpongeCRH<RescuePrimePermutation>;
+
+    type LeafHashGadget = SpongeCRHGadget<RescuePrimePermutation>;
+    type TwoToOneHashGadget = SpongeCRHGadget<RescuePrimePermutation>;
+
+    fn default_parameters() -> Self::Parameters {
+        RescuePrimePermutation::default_parameters()
+    }
+}
+
+impl Permutation for RescuePrimePermutation {
+    type Parameters = RescuePrimeParameters;
+
+    fn default_parameters() -> Self::Parameters {
+        RescuePrimeParameters::new(DEFAULT_SECURITY_LEVEL)
+    }
+
+    fn permute(params: &Self::Parameters, state: &mut [ConstraintField; WIDTH]) {
+        for [forward, backward] in &params.round_constants {
+            for cell in state.iter_mut() {
+                *cell = cell.pow([params.alpha]);
+            }
+            *state = mds_layer(params, state, forward);
+            for cell in state.iter_mut() {
+                *cell = cell.pow(&params.alpha_inv);
+            }
+            *state = mds_layer(params, state, backward);
+        }
+    }
+
+    fn permute_var(
+        params: &Self::Parameters,
+        state: &mut [FpVar<ConstraintField>; WIDTH],
+    ) -> Result<(), SynthesisError> {
+        for [forward, backward] in &params.round_constants {
+            for cell in state.iter_mut() {
+                *cell = cell.pow_by_constant([params.alpha])?;
+            }
+            *state = mds_layer_var(params, state, forward);
+            for cell in state.iter_mut() {
+                *cell = inverse_sbox_var(params, cell)?;
+            }
+            *state = mds_layer_var(params, state, backward);
+        }
+        Ok(())
+    }
+}
+
+fn mds_layer(
+    params: &RescuePrimeParameters,
+    state: &[ConstraintField; WIDTH],
+    constants: &[ConstraintField; WIDTH],
+) -> [ConstraintField; WIDTH] {
+    core::array::from_fn(|i| {
+        params.mds[i]
+            .iter()
+            .zip(state)
+            .map(|(m, x)| *m * x)
+            .sum::<ConstraintField>()
+            + constants[i]
+    })
+}
+
+fn mds_layer_var(
+    params: &RescuePrimeParameters,
+    state: &[FpVar<ConstraintField>; WIDTH],
+    constants: &[ConstraintField; WIDTH],
+) -> [FpVar<ConstraintField>; WIDTH] {
+    core::array::from_fn(|i| {
+        params.mds[i]
+            .iter()
+            .zip(state)
+            .map(|(m, x)| x * *m)
+            .sum::<FpVar<ConstraintField>>()
+            + constants[i]
+    })
+}
+
+/// Witnesses `y = x^(1/alpha)` and enforces `y^alpha = x`.
+fn inverse_sbox_var(
+    params: &RescuePrimeParameters,
+    x: &FpVar<ConstraintField>,
+) -> Result<FpVar<ConstraintField>, SynthesisError> {
+    let y = match x {
+        FpVar::Constant(value) => FpVar::constant(value.pow(&params.alpha_inv)),
+        FpVar::Var(_) => FpVar::new_witness(x.cs(), || Ok(x.value()?.pow(&params.alpha_inv)))?,
+    };
+    y.pow_by_constant([params.alpha])?.enforce_equal(x)?;
+    Ok(y)
+}
+
+/// Rounds from the spec's `get_number_of_rounds`: the first `l1` whose Gröbner-basis cost
+/// exceeds the security level, plus 50%, and at least 5 before the margin.
+fn rescue_rounds(security_level: u32, alpha: u64) -> usize {
+    let m = WIDTH as f64;
+    let rate = RATE as f64;
+    let dcon = |n: f64| (0.5 * (alpha as f64 - 1.0) * m * (n - 1.0) + 2.0).floor();
+    let v = |n: f64| m * (n - 1.0) + rate;
+    let l1 = (1..25)
+        .find(|&l| {
+            let l = l as f64;
+            2.0 * log2_binomial((v(l) + dcon(l)) as u64, v(l) as u64) > security_level as f64
+        })
+        .unwrap_or(25);
+    (1.5 * l1.max(5) as f64).ceil() as usize
+}
+
+/// `alpha^-1 mod (p - 1)`: the `(k (p - 1) + 1) / alpha` that is an integer, for some `k < alpha`.
+fn inverse_exponent(alpha: u64) -> Option<Vec<u64>> {
+    let mut p_minus_one = ConstraintField::MODULUS.0;
+    p_minus_one[0] -= 1; // p is odd
+    (1..alpha).find_map(|k| {
+        // n = k (p - 1) + 1 needs one limb more than p
+        let mut n = [0u64; 5];
+        let mut carry = 1u128;
+        for (limb, p_limb) in n.iter_mut().zip(p_minus_one) {
+            let value = p_limb as u128 * k as u128 + carry;
+            *limb = value as u64;
+            carry = value >> 64;
+        }
+        n[4] = carry as u64;
+
+        let mut quotient = [0u64; 5];
+        let mut remainder = 0u128;
+        for (q, limb) in quotient.iter_mut().zip(n).rev() {
+            let current = (remainder << 64) | limb as u128;
+            *q = (current / alpha as u128) as u64;
+            remainder = current % alpha as u128;
+        }
+        (remainder == 0).then(|| quotient[..4].to_vec())
+    })
+}
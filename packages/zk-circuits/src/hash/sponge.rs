@@ -0,0 +1,207 @@
+//! Fixed-width sponge hashing over a generic permutation, shared by the Poseidon2 and
+//! Rescue-Prime backends.
+//!
+//! The state has [`WIDTH`] elements: one capacity element, initialised to the input length for
+//! domain separation, and [`RATE`] rate elements. Inputs are absorbed `RATE` at a time with a
+//! permutation after each block, and the digest is the first rate element. Two-to-one hashing is
+//! the sponge over `[left, right]`.
+
+use ark_crypto_primitives::{
+    crh::{CRHScheme, CRHSchemeGadget, TwoToOneCRHScheme, TwoToOneCRHSchemeGadget},
+    Error,
+};
+use ark_ff::{PrimeField, Zero};
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    fields::{fp::FpVar, FieldVar},
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{borrow::Borrow, fmt::Debug, marker::PhantomData, rand::Rng, vec::Vec};
+use sha2::{Digest, Sha512};
+
+use crate::types::ConstraintField;
+
+/// State width of every sponge permutation in this module.
+pub const WIDTH: usize = 3;
+/// Field elements absorbed per permutation call.
+pub const RATE: usize = WIDTH - 1;
+
+/// A permutation of `WIDTH` field elements with a matching R1CS gadget.
+pub trait Permutation: Clone + Debug + Send + Sync + 'static {
+    type Parameters: Clone + Debug + CanonicalSerialize + CanonicalDeserialize + Send + Sync;
+
+    fn default_parameters() -> Self::Parameters;
+
+    fn permute(params: &Self::Parameters, state: &mut [ConstraintField; WIDTH]);
+
+    fn permute_var(
+        params: &Self::Parameters,
+        state: &mut [FpVar<ConstraintField>; WIDTH],
+    ) -> Result<(), SynthesisError>;
+}
+
+/// Native sponge hash; implements both the leaf and the two-to-one CRH traits.
+#[derive(Clone, Debug)]
+pub struct SpongeCRH<P: Permutation>(PhantomData<P>);
+
+/// In-circuit counterpart of [`SpongeCRH`].
+#[derive(Clone, Debug)]
+pub struct SpongeCRHGadget<P: Permutation>(PhantomData<P>);
+
+/// Permutation parameters allocated as circuit constants.
+#[derive(Clone, Debug)]
+pub struct SpongeParametersVar<P: Permutation> {
+    pub parameters: P::Parameters,
+}
+
+/// Hashes `input` with the sponge over `P`.
+pub fn hash<P: Permutation>(params: &P::Parameters, input: &[ConstraintField]) -> ConstraintField {
+    let mut state = [
+        ConstraintField::from(input.len() as u64),
+        ConstraintField::zero(),
+        ConstraintField::zero(),
+    ];
+    if input.is_empty() {
+        P::permute(params, &mut state);
+    }
+    for block in input.chunks(RATE) {
+        for (cell, value) in state[1..].iter_mut().zip(block) {
+            *cell += value;
+        }
+        P::permute(params, &mut state);
+    }
+    state[1]
+}
+
+/// Gadget for [`hash`].
+pub fn hash_var<P: Permutation>(
+    params: &P::Parameters,
+    input: &[FpVar<ConstraintField>],
+) -> Result<FpVar<ConstraintField>, SynthesisError> {
+    let mut state = [
+        FpVar::constant(ConstraintField::from(input.len() as u64)),
+        FpVar::zero(),
+        FpVar::zero(),
+    ];
+    if input.is_empty() {
+        P::permute_var(params, &mut state)?;
+    }
+    for block in input.chunks(RATE) {
+        for (cell, value) in state[1..].iter_mut().zip(block) {
+            *cell += value;
+        }
+        P::permute_var(params, &mut state)?;
+    }
+    let [_, digest, _] = state;
+    Ok(digest)
+}
+
+/// Derives `count` constants as `SHA-512(label || index) mod p`.
+pub(crate) fn derive_constants(label: &[u8], count: usize) -> Vec<ConstraintField> {
+    (0..count as u64)
+        .map(|index| {
+            let digest = Sha512::new()
+                .chain_update(label)
+                .chain_update(index.to_le_bytes())
+                .finalize();
+            ConstraintField::from_le_bytes_mod_order(&digest)
+        })
+        .collect()
+}
+
+impl<P: Permutation> CRHScheme for SpongeCRH<P> {
+    type Input = [ConstraintField];
+    type Output = ConstraintField;
+    type Parameters = P::Parameters;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(P::default_parameters())
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        input: T,
+    ) -> Result<Self::Output, Error> {
+        Ok(hash::<P>(parameters, input.borrow()))
+    }
+}
+
+impl<P: Permutation> TwoToOneCRHScheme for SpongeCRH<P> {
+    type Input = ConstraintField;
+    type Output = ConstraintField;
+    type Parameters = P::Parameters;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(P::default_parameters())
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Ok(hash::<P>(
+            parameters,
+            &[*left_input.borrow(), *right_input.borrow()],
+        ))
+    }
+}
+
+impl<P: Permutation> CRHSchemeGadget<SpongeCRH<P>, ConstraintField> for SpongeCRHGadget<P> {
+    type InputVar = [FpVar<ConstraintField>];
+    type OutputVar = FpVar<ConstraintField>;
+    type ParametersVar = SpongeParametersVar<P>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        hash_var::<P>(&parameters.parameters, input)
+    }
+}
+
+impl<P: Permutation> TwoToOneCRHSchemeGadget<SpongeCRH<P>, ConstraintField> for SpongeCRHGadget<P> {
+    type InputVar = FpVar<ConstraintField>;
+    type OutputVar = FpVar<ConstraintField>;
+    type ParametersVar = SpongeParametersVar<P>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::InputVar,
+        right_input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::OutputVar,
+        right_input: &Self::OutputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        hash_var::<P>(
+            &parameters.parameters,
+            &[left_input.clone(), right_input.clone()],
+        )
+    }
+}
+
+impl<P: Permutation> AllocVar<P::Parameters, ConstraintField> for SpongeParametersVar<P> {
+    fn new_variable<T: Borrow<P::Parameters>>(
+        _cs: impl Into<Namespace<ConstraintField>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        _mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        f().map(|params| Self {
+            parameters: params.borrow().clone(),
+        })
+    }
+}
@@ -0,0 +1,434 @@
+//! Versioned container format for the proving key, verifying key and Poseidon parameters.
+//!
+//! Each of `gacha_pk.bin`, `gacha_vk.bin` and `params.bin` is a [`KeyFileHeader`] followed by the
+//! compressed arkworks payload. The header records what the payload is, which curve and tree
+//! height it was generated for, and fingerprints of the Poseidon parameters and verifying key, so
+//! files from different `generate_crs` runs can never be mixed without an error at load time.
+//!
+//! | field                  | size | meaning                                        |
+//! |------------------------|------|------------------------------------------------|
+//! | `magic`                | 8    | `ZKGACHA\0`                                    |
+//! | `version`              | 2    | [`KEY_FILE_VERSION`]                           |
+//! | `kind`                 | 1    | [`KeyFileKind`]                                |
+//! | `curve_id`             | 1    | [`CURVE_ID_BLS12_381`]                         |
+//! | `tree_height`          | 4    | Merkle tree has `2^tree_height` leaves         |
+//! | `poseidon_params_hash` | 32   | SHA-256 of the compressed Poseidon parameters  |
+//! | `vk_fingerprint`       | 32   | SHA-256 of the compressed verifying key        |
+//! | `payload_digest`       | 32   | SHA-256 of the payload that follows            |
+//!
+//! All integers are little-endian.
+//!
+//! [`VerifierKeys`] loads just `gacha_vk.bin` and `params.bin` and is available in the
+//! verifier-only build; [`GachaKeys`] needs the `prover` feature. Both can `decode` file contents
+//! or `load` them from the directory `generate_crs` wrote.
+
+use ark_bls12_381::Bls12_381;
+#[cfg(feature = "prover")]
+use ark_groth16::ProvingKey;
+use ark_groth16::VerifyingKey;
+#[cfg(feature = "prover")]
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisMode,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{format, string::ToString, vec::Vec};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+#[cfg(feature = "prover")]
+use crate::circuit::UserPullCircuit;
+use crate::error::GachaCircuitError;
+#[cfg(feature = "prover")]
+use crate::hash::PoseidonHash;
+#[cfg(feature = "prover")]
+use crate::types::NativeGachaCircuitInputs;
+use crate::types::{
+    prepare_groth16_public_inputs, ConstraintField, NativePoseidonConfig, MAX_TREE_HEIGHT,
+};
+use crate::utils::serialization::{deserialize_compressed, DeserializationPolicy, ValueKind};
+
+pub const KEY_FILE_MAGIC: [u8; 8] = *b"ZKGACHA\0";
+pub const KEY_FILE_VERSION: u16 = 1;
+pub const CURVE_ID_BLS12_381: u8 = 1;
+
+/// What a key file contains.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum KeyFileKind {
+    PoseidonParams = 1,
+    ProvingKey = 2,
+    VerifyingKey = 3,
+}
+
+impl KeyFileKind {
+    /// The name `generate_crs` writes this file under.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            KeyFileKind::PoseidonParams => "params.bin",
+            KeyFileKind::ProvingKey => "gacha_pk.bin",
+            KeyFileKind::VerifyingKey => "gacha_vk.bin",
+        }
+    }
+
+    /// What the payload is made of; see [`deserialize_compressed`].
+    pub fn value_kind(self) -> ValueKind {
+        match self {
+            KeyFileKind::PoseidonParams => ValueKind::FieldElements,
+            KeyFileKind::ProvingKey | KeyFileKind::VerifyingKey => ValueKind::CurvePoints,
+        }
+    }
+}
+
+/// Header written in front of every key file payload.
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KeyFileHeader {
+    pub magic: [u8; 8],
+    pub version: u16,
+    pub kind: u8,
+    pub curve_id: u8,
+    pub tree_height: u32,
+    pub poseidon_params_hash: [u8; 32],
+    pub vk_fingerprint: [u8; 32],
+    pub payload_digest: [u8; 32],
+}
+
+/// SHA-256 of the compressed Poseidon parameters.
+pub fn poseidon_params_hash(params: &NativePoseidonConfig) -> Result<[u8; 32], GachaCircuitError> {
+    sha256_of(params)
+}
+
+/// SHA-256 of the compressed verifying key.
+pub fn vk_fingerprint(vk: &VerifyingKey<Bls12_381>) -> Result<[u8; 32], GachaCircuitError> {
+    sha256_of(vk)
+}
+
+/// Wraps `payload` in a key file.
+pub fn encode_key_file<T: CanonicalSerialize>(
+    kind: KeyFileKind,
+    tree_height: usize,
+    poseidon_params_hash: [u8; 32],
+    vk_fingerprint: [u8; 32],
+    payload: &T,
+) -> Result<Vec<u8>, GachaCircuitError> {
+    let tree_height = u32::try_from(tree_height)
+        .map_err(|_| GachaCircuitError::InvalidInput("Tree height too large".to_string()))?;
+
+    let mut payload_bytes = Vec::new();
+    payload.serialize_compressed(&mut payload_bytes)?;
+
+    let header = KeyFileHeader {
+        magic: KEY_FILE_MAGIC,
+        version: KEY_FILE_VERSION,
+        kind: kind as u8,
+        curve_id: CURVE_ID_BLS12_381,
+        tree_height,
+        poseidon_params_hash,
+        vk_fingerprint,
+        payload_digest: Sha256::digest(&payload_bytes).into(),
+    };
+
+    let mut bytes = Vec::with_capacity(header.compressed_size() + payload_bytes.len());
+    header.serialize_compressed(&mut bytes)?;
+    bytes.extend_from_slice(&payload_bytes);
+    Ok(bytes)
+}
+
+/// Parses a key file of the expected `kind`, checking its header and payload digest.
+/// Points in the payload are validated according to `policy`.
+pub fn decode_key_file<T: CanonicalDeserialize>(
+    kind: KeyFileKind,
+    bytes: &[u8],
+    policy: DeserializationPolicy,
+) -> Result<(KeyFileHeader, T), GachaCircuitError> {
+    if bytes.len() < KEY_FILE_MAGIC.len() || bytes[..KEY_FILE_MAGIC.len()] != KEY_FILE_MAGIC {
+        return Err(GachaCircuitError::InvalidKeyFile(format!(
+            "{:?} file is missing the key file magic (raw arkworks blob from an older generate_crs?)",
+            kind
+        )));
+    }
+
+    let mut reader = bytes;
+    let header = KeyFileHeader::deserialize_compressed(&mut reader).map_err(|e| {
+        GachaCircuitError::InvalidKeyFile(format!("{:?} file has a malformed header: {}", kind, e))
+    })?;
+    if header.version != KEY_FILE_VERSION {
+        return Err(GachaCircuitError::InvalidKeyFile(format!(
+            "Unsupported key file version {} (expected {})",
+            header.version, KEY_FILE_VERSION
+        )));
+    }
+    if header.kind != kind as u8 {
+        return Err(GachaCircuitError::InvalidKeyFile(format!(
+            "Expected a {:?} file, found kind {}",
+            kind, header.kind
+        )));
+    }
+    if header.curve_id != CURVE_ID_BLS12_381 {
+        return Err(GachaCircuitError::InvalidKeyFile(format!(
+            "Unsupported curve id {}",
+            header.curve_id
+        )));
+    }
+    if header.tree_height == 0 || header.tree_height as usize > MAX_TREE_HEIGHT {
+        return Err(GachaCircuitError::InvalidKeyFile(format!(
+            "Tree height {} is outside 1..={}",
+            header.tree_height, MAX_TREE_HEIGHT
+        )));
+    }
+    let payload_digest: [u8; 32] = Sha256::digest(reader).into();
+    if payload_digest != header.payload_digest {
+        return Err(GachaCircuitError::InvalidKeyFile(format!(
+            "{:?} payload is corrupted (digest mismatch)",
+            kind
+        )));
+    }
+
+    let payload =
+        deserialize_compressed(&format!("{:?}", kind), reader, kind.value_kind(), policy)?;
+    Ok((header, payload))
+}
+
+/// The verifying half of [`GachaKeys`]: everything needed to check proofs.
+#[derive(Clone, Debug)]
+pub struct VerifierKeys {
+    pub verifying_key: VerifyingKey<Bls12_381>,
+    pub poseidon_params: NativePoseidonConfig,
+    /// The Merkle tree has `2^tree_height` leaves
+    pub tree_height: usize,
+}
+
+impl VerifierKeys {
+    /// Decodes and cross-checks `gacha_vk.bin` and `params.bin`.
+    ///
+    /// Rejects files from different `generate_crs` runs, payloads that do not match the
+    /// recorded fingerprints, and verifying keys for a different number of public inputs.
+    pub fn decode(
+        vk_bytes: &[u8],
+        params_bytes: &[u8],
+        policy: DeserializationPolicy,
+    ) -> Result<Self, GachaCircuitError> {
+        Self::decode_with_header(vk_bytes, params_bytes, policy).map(|(_, keys)| keys)
+    }
+
+    /// [`decode`](Self::decode), also returning the `params.bin` header for further checks.
+    fn decode_with_header(
+        vk_bytes: &[u8],
+        params_bytes: &[u8],
+        policy: DeserializationPolicy,
+    ) -> Result<(KeyFileHeader, Self), GachaCircuitError> {
+        let (params_header, poseidon_params): (_, NativePoseidonConfig) =
+            decode_key_file(KeyFileKind::PoseidonParams, params_bytes, policy)?;
+        let (vk_header, verifying_key): (_, VerifyingKey<Bls12_381>) =
+            decode_key_file(KeyFileKind::VerifyingKey, vk_bytes, policy)?;
+
+        check_same_setup(&params_header, &vk_header)?;
+        if poseidon_params_hash(&poseidon_params)? != params_header.poseidon_params_hash {
+            return Err(GachaCircuitError::InvalidKeyFile(
+                "Poseidon parameters do not match the recorded hash".to_string(),
+            ));
+        }
+        if vk_fingerprint(&verifying_key)? != params_header.vk_fingerprint {
+            return Err(GachaCircuitError::InvalidKeyFile(
+                "Verifying key does not match the recorded fingerprint".to_string(),
+            ));
+        }
+        // One entry for the constant `1` plus one per public input
+        let num_public_inputs = prepare_groth16_public_inputs(ConstraintField::from(0u64)).len();
+        if verifying_key.gamma_abc_g1.len() != num_public_inputs + 1 {
+            return Err(GachaCircuitError::InvalidKeyFile(format!(
+                "Verifying key has {} public inputs, expected {}",
+                verifying_key.gamma_abc_g1.len().saturating_sub(1),
+                num_public_inputs
+            )));
+        }
+
+        let tree_height = params_header.tree_height as usize;
+        Ok((
+            params_header,
+            Self {
+                verifying_key,
+                poseidon_params,
+                tree_height,
+            },
+        ))
+    }
+
+    /// Reads `gacha_vk.bin` and `params.bin` from `dir` and decodes them with checked points.
+    #[cfg(feature = "std")]
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, GachaCircuitError> {
+        let dir = dir.as_ref();
+        Self::decode(
+            &read_key_file(dir, KeyFileKind::VerifyingKey)?,
+            &read_key_file(dir, KeyFileKind::PoseidonParams)?,
+            DeserializationPolicy::Checked,
+        )
+    }
+}
+
+/// The key material for one circuit configuration, as produced by `generate_crs`.
+#[cfg(feature = "prover")]
+#[derive(Clone, Debug)]
+pub struct GachaKeys {
+    pub proving_key: ProvingKey<Bls12_381>,
+    pub verifying_key: VerifyingKey<Bls12_381>,
+    pub poseidon_params: NativePoseidonConfig,
+    /// The Merkle tree has `2^tree_height` leaves
+    pub tree_height: usize,
+}
+
+/// The three key files of a [`GachaKeys`].
+#[derive(Clone, Debug)]
+pub struct EncodedGachaKeys {
+    pub proving_key: Vec<u8>,
+    pub verifying_key: Vec<u8>,
+    pub poseidon_params: Vec<u8>,
+}
+
+#[cfg(feature = "prover")]
+impl GachaKeys {
+    /// Encodes the keys as `gacha_pk.bin`, `gacha_vk.bin` and `params.bin` contents.
+    pub fn encode(&self) -> Result<EncodedGachaKeys, GachaCircuitError> {
+        let params_hash = poseidon_params_hash(&self.poseidon_params)?;
+        let fingerprint = vk_fingerprint(&self.verifying_key)?;
+        Ok(EncodedGachaKeys {
+            proving_key: encode_key_file(
+                KeyFileKind::ProvingKey,
+                self.tree_height,
+                params_hash,
+                fingerprint,
+                &self.proving_key,
+            )?,
+            verifying_key: encode_key_file(
+                KeyFileKind::VerifyingKey,
+                self.tree_height,
+                params_hash,
+                fingerprint,
+                &self.verifying_key,
+            )?,
+            poseidon_params: encode_key_file(
+                KeyFileKind::PoseidonParams,
+                self.tree_height,
+                params_hash,
+                fingerprint,
+                &self.poseidon_params,
+            )?,
+        })
+    }
+
+    /// Decodes and cross-checks the three key files.
+    ///
+    /// Beyond the per-file header checks, this rejects files from different `generate_crs`
+    /// runs, a proving key that does not embed the verifying key, and keys whose circuit size
+    /// does not match the recorded tree height.
+    pub fn decode(
+        pk_bytes: &[u8],
+        vk_bytes: &[u8],
+        params_bytes: &[u8],
+        policy: DeserializationPolicy,
+    ) -> Result<Self, GachaCircuitError> {
+        // 1. The verifying key and parameters must be consistent on their own
+        let (
+            params_header,
+            VerifierKeys {
+                verifying_key,
+                poseidon_params,
+                tree_height,
+            },
+        ) = VerifierKeys::decode_with_header(vk_bytes, params_bytes, policy)?;
+        let (pk_header, proving_key): (_, ProvingKey<Bls12_381>) =
+            decode_key_file(KeyFileKind::ProvingKey, pk_bytes, policy)?;
+
+        // 2. The proving key must come from the same setup and embed the verifying key
+        check_same_setup(&params_header, &pk_header)?;
+        if vk_fingerprint(&proving_key.vk)? != params_header.vk_fingerprint {
+            return Err(GachaCircuitError::InvalidKeyFile(
+                "Verifying key does not match the recorded fingerprint".to_string(),
+            ));
+        }
+
+        // 3. The keys must be for a UserPullCircuit of the recorded tree height
+        let (num_instance_variables, num_variables) = circuit_size(&poseidon_params, tree_height)?;
+        if verifying_key.gamma_abc_g1.len() != num_instance_variables
+            || proving_key.a_query.len() != num_variables
+        {
+            return Err(GachaCircuitError::InvalidKeyFile(format!(
+                "Keys were not generated for a tree of height {}",
+                tree_height
+            )));
+        }
+
+        Ok(Self {
+            proving_key,
+            verifying_key,
+            poseidon_params,
+            tree_height,
+        })
+    }
+
+    /// Reads `gacha_pk.bin`, `gacha_vk.bin` and `params.bin` from `dir` (the `generate_crs`
+    /// output directory) and decodes them with checked points. For a trusted proving key that is
+    /// slow to check, read the files yourself and [`decode`](Self::decode) them unchecked.
+    #[cfg(feature = "std")]
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, GachaCircuitError> {
+        let dir = dir.as_ref();
+        Self::decode(
+            &read_key_file(dir, KeyFileKind::ProvingKey)?,
+            &read_key_file(dir, KeyFileKind::VerifyingKey)?,
+            &read_key_file(dir, KeyFileKind::PoseidonParams)?,
+            DeserializationPolicy::Checked,
+        )
+    }
+}
+
+/// Reads the `kind` file from `dir`.
+#[cfg(feature = "std")]
+fn read_key_file(dir: &Path, kind: KeyFileKind) -> Result<Vec<u8>, GachaCircuitError> {
+    let path = dir.join(kind.file_name());
+    std::fs::read(&path).map_err(|e| {
+        GachaCircuitError::InvalidKeyFile(format!("Cannot read {}: {}", path.display(), e))
+    })
+}
+
+/// Errors unless both headers were written by the same `generate_crs` run.
+fn check_same_setup(
+    params_header: &KeyFileHeader,
+    header: &KeyFileHeader,
+) -> Result<(), GachaCircuitError> {
+    if header.tree_height != params_header.tree_height
+        || header.poseidon_params_hash != params_header.poseidon_params_hash
+        || header.vk_fingerprint != params_header.vk_fingerprint
+    {
+        return Err(GachaCircuitError::InvalidKeyFile(
+            "Proving key, verifying key and parameters come from different setups".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// `(instance variables, all variables)` of a `UserPullCircuit`, as counted by Groth16 setup.
+#[cfg(feature = "prover")]
+fn circuit_size(
+    poseidon_params: &NativePoseidonConfig,
+    tree_height: usize,
+) -> Result<(usize, usize), GachaCircuitError> {
+    let cs = ConstraintSystem::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Constraints);
+    cs.set_mode(SynthesisMode::Setup);
+    UserPullCircuit::<PoseidonHash>::new(
+        NativeGachaCircuitInputs::placeholder(tree_height),
+        poseidon_params.clone(),
+    )
+    .generate_constraints(cs.clone())?;
+    cs.finalize();
+    Ok((
+        cs.num_instance_variables(),
+        cs.num_instance_variables() + cs.num_witness_variables(),
+    ))
+}
+
+fn sha256_of<T: CanonicalSerialize + ?Sized>(value: &T) -> Result<[u8; 32], GachaCircuitError> {
+    let mut bytes = Vec::new();
+    value.serialize_compressed(&mut bytes)?;
+    Ok(Sha256::digest(&bytes).into())
+}
@@ -0,0 +1,195 @@
+//! The native Rust API: load the keys `generate_crs` wrote, prove pulls and verify them without
+//! assembling `Groth16` calls, keys and public inputs by hand.
+//!
+//! ```no_run
+//! # #[cfg(all(feature = "prover", feature = "std"))]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use zk_circuits::native::GachaProver;
+//! use zk_circuits::tree::{GachaLeaf, GachaTree};
+//! use zk_circuits::utils::get_rng;
+//!
+//! let prover = GachaProver::load("./output")?;
+//! let mut rng = get_rng(None)?;
+//! let leaves = vec![GachaLeaf::random(&mut rng)];
+//! let params = prover.pool().poseidon_params();
+//! let tree = GachaTree::build(params, prover.tree_height(), leaves, &mut rng)?;
+//! let inputs = tree.circuit_inputs(0)?;
+//!
+//! let proof = prover.prove(&inputs)?;
+//! assert!(prover.verifier().verify(inputs.merkle_root, &proof)?);
+//! # Ok(())
+//! # }
+//! # #[cfg(not(all(feature = "prover", feature = "std")))]
+//! # fn main() {}
+//! ```
+//!
+//! Both types hold their keys as an `Arc<GachaPool>`, so clones are cheap; the `GachaProver` /
+//! `GachaVerifier` JS classes and the pool registry functions are thin wrappers over them.
+
+use ark_bls12_381::Bls12_381;
+use ark_groth16::Proof;
+#[cfg(feature = "prover")]
+use ark_std::rand::{CryptoRng, RngCore};
+use ark_std::{format, rand::rngs::StdRng, sync::Arc};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::batch::BatchVerificationResult;
+use crate::envelope::GachaProofEnvelope;
+use crate::error::GachaCircuitError;
+#[cfg(feature = "prover")]
+use crate::keys::GachaKeys;
+use crate::keys::VerifierKeys;
+use crate::pool::GachaPool;
+#[cfg(feature = "prover")]
+use crate::prover::ProvePhase;
+use crate::types::ConstraintField;
+#[cfg(feature = "prover")]
+use crate::types::NativeGachaCircuitInputs;
+use crate::utils::get_rng;
+
+/// Proves pulls (and verifies them) with one set of keys.
+#[cfg(feature = "prover")]
+#[derive(Clone, Debug)]
+pub struct GachaProver {
+    pool: Arc<GachaPool>,
+}
+
+#[cfg(feature = "prover")]
+impl GachaProver {
+    pub fn new(keys: GachaKeys) -> Result<Self, GachaCircuitError> {
+        Ok(Self {
+            pool: Arc::new(GachaPool::from_keys(keys)?),
+        })
+    }
+
+    /// Loads the keys from the `generate_crs` output directory; see [`GachaKeys::load`].
+    #[cfg(feature = "std")]
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, GachaCircuitError> {
+        Self::new(GachaKeys::load(dir)?)
+    }
+
+    /// A prover over an already loaded pool; errors if it has no proving key.
+    pub fn from_pool(pool: Arc<GachaPool>) -> Result<Self, GachaCircuitError> {
+        pool.proving_key()?;
+        Ok(Self { pool })
+    }
+
+    pub fn pool(&self) -> &Arc<GachaPool> {
+        &self.pool
+    }
+
+    /// The Merkle tree has `2^tree_height` leaves.
+    pub fn tree_height(&self) -> usize {
+        self.pool.tree_height()
+    }
+
+    /// Proves one pull with a freshly seeded RNG. The inputs are checked first, so a bad
+    /// witness fails fast; see [`NativeGachaCircuitInputs::validate`].
+    pub fn prove(
+        &self,
+        inputs: &NativeGachaCircuitInputs,
+    ) -> Result<Proof<Bls12_381>, GachaCircuitError> {
+        self.prove_with_rng(inputs, &mut os_rng()?)
+    }
+
+    /// [`prove`](Self::prove) with the caller's RNG, e.g. a seeded one for reproducible proofs.
+    pub fn prove_with_rng<R: RngCore + CryptoRng>(
+        &self,
+        inputs: &NativeGachaCircuitInputs,
+        rng: &mut R,
+    ) -> Result<Proof<Bls12_381>, GachaCircuitError> {
+        self.pool.prove(inputs.clone(), rng)
+    }
+
+    /// [`prove`](Self::prove), calling `on_phase` before each phase; an error from it stops
+    /// proving. See [`crate::prover::prove_pull_with_progress`].
+    pub fn prove_with_progress(
+        &self,
+        inputs: &NativeGachaCircuitInputs,
+        on_phase: impl FnMut(ProvePhase) -> Result<(), GachaCircuitError>,
+    ) -> Result<Proof<Bls12_381>, GachaCircuitError> {
+        self.pool
+            .prove_with_progress(inputs.clone(), &mut os_rng()?, on_phase)
+    }
+
+    /// Checks that `inputs` lead to their Merkle root without proving.
+    pub fn verify_merkle_path(
+        &self,
+        inputs: &NativeGachaCircuitInputs,
+    ) -> Result<bool, GachaCircuitError> {
+        self.pool.verify_merkle_path(inputs)
+    }
+
+    /// A verifier sharing this prover's keys.
+    pub fn verifier(&self) -> GachaVerifier {
+        GachaVerifier {
+            pool: self.pool.clone(),
+        }
+    }
+}
+
+/// Verifies pull proofs with one verifying key; available in the verifier-only build.
+#[derive(Clone, Debug)]
+pub struct GachaVerifier {
+    pool: Arc<GachaPool>,
+}
+
+impl GachaVerifier {
+    pub fn new(keys: VerifierKeys) -> Result<Self, GachaCircuitError> {
+        Ok(Self {
+            pool: Arc::new(GachaPool::from_verifier_keys(keys)?),
+        })
+    }
+
+    /// Loads `gacha_vk.bin` and `params.bin` from `dir`; see [`VerifierKeys::load`].
+    #[cfg(feature = "std")]
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, GachaCircuitError> {
+        Self::new(VerifierKeys::load(dir)?)
+    }
+
+    /// A verifier over an already loaded pool, with or without a proving key.
+    pub fn from_pool(pool: Arc<GachaPool>) -> Self {
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> &Arc<GachaPool> {
+        &self.pool
+    }
+
+    /// The Merkle tree has `2^tree_height` leaves.
+    pub fn tree_height(&self) -> usize {
+        self.pool.tree_height()
+    }
+
+    /// Verifies a pull proof against the Merkle root it was made for.
+    pub fn verify(
+        &self,
+        merkle_root: ConstraintField,
+        proof: &Proof<Bls12_381>,
+    ) -> Result<bool, GachaCircuitError> {
+        self.pool.verify(merkle_root, proof)
+    }
+
+    /// Verifies an envelope made with this verifier's keys; its pool id is ignored.
+    pub fn verify_envelope(
+        &self,
+        envelope: &GachaProofEnvelope,
+    ) -> Result<bool, GachaCircuitError> {
+        self.pool.verify_envelope(envelope)
+    }
+
+    /// Batch-verifies `proofs[i]` against `merkle_roots[i]`; see
+    /// [`crate::batch::verify_gacha_proofs_batch`].
+    pub fn verify_batch(
+        &self,
+        merkle_roots: &[ConstraintField],
+        proofs: &[Proof<Bls12_381>],
+    ) -> Result<BatchVerificationResult, GachaCircuitError> {
+        self.pool.verify_batch(merkle_roots, proofs, &mut os_rng()?)
+    }
+}
+
+fn os_rng() -> Result<StdRng, GachaCircuitError> {
+    get_rng(None).map_err(|e| GachaCircuitError::SetupError(format!("Failed to get RNG: {}", e)))
+}
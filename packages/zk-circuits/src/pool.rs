@@ -0,0 +1,259 @@
+//! Gacha pools: independently loaded key sets, so one page can serve several banners or swap
+//! to a new season's keys without reloading the module.
+//!
+//! A [`GachaPool`] owns the prepared verifying key and Poseidon parameters of one `generate_crs`
+//! run, plus its proving key when loaded for proving. A [`PoolRegistry`] maps pool ids to pools.
+//! Pools are handed out as `Arc`s, so unloading or replacing a pool never disturbs a proof that
+//! is already running against it.
+
+use ark_bls12_381::Bls12_381;
+#[cfg(feature = "prover")]
+use ark_groth16::ProvingKey;
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof};
+use ark_snark::SNARK;
+#[cfg(feature = "prover")]
+use ark_std::rand::CryptoRng;
+use ark_std::{
+    collections::BTreeMap,
+    format,
+    rand::RngCore,
+    string::{String, ToString},
+    sync::Arc,
+    vec::Vec,
+};
+
+use crate::batch::{self, BatchVerificationResult};
+use crate::envelope::GachaProofEnvelope;
+use crate::error::GachaCircuitError;
+#[cfg(feature = "prover")]
+use crate::keys::GachaKeys;
+use crate::keys::{vk_fingerprint, VerifierKeys};
+#[cfg(feature = "prover")]
+use crate::prover::{self, ProvePhase};
+#[cfg(feature = "prover")]
+use crate::types::NativeGachaCircuitInputs;
+use crate::types::{prepare_groth16_public_inputs, ConstraintField, NativePoseidonConfig};
+use crate::utils::serialization::DeserializationPolicy;
+
+/// The pool `init_gacha_keys` and `init_verifier` load, and the pool-less entry points use.
+pub const DEFAULT_POOL_ID: &str = "default";
+
+/// The keys of one gacha pool.
+#[derive(Clone, Debug)]
+pub struct GachaPool {
+    #[cfg(feature = "prover")]
+    proving_key: Option<ProvingKey<Bls12_381>>,
+    verifying_key: PreparedVerifyingKey<Bls12_381>,
+    vk_fingerprint: [u8; 32],
+    poseidon_params: NativePoseidonConfig,
+    tree_height: usize,
+}
+
+impl GachaPool {
+    /// A pool that can prove and verify.
+    #[cfg(feature = "prover")]
+    pub fn from_keys(keys: GachaKeys) -> Result<Self, GachaCircuitError> {
+        let mut pool = Self::from_verifier_keys(VerifierKeys {
+            verifying_key: keys.verifying_key,
+            poseidon_params: keys.poseidon_params,
+            tree_height: keys.tree_height,
+        })?;
+        pool.proving_key = Some(keys.proving_key);
+        Ok(pool)
+    }
+
+    /// A pool that can only verify.
+    pub fn from_verifier_keys(keys: VerifierKeys) -> Result<Self, GachaCircuitError> {
+        let verifying_key = Groth16::<Bls12_381>::process_vk(&keys.verifying_key)
+            .map_err(|e| GachaCircuitError::SetupError(format!("Failed to prepare VK: {}", e)))?;
+        Ok(Self {
+            #[cfg(feature = "prover")]
+            proving_key: None,
+            vk_fingerprint: vk_fingerprint(&keys.verifying_key)?,
+            verifying_key,
+            poseidon_params: keys.poseidon_params,
+            tree_height: keys.tree_height,
+        })
+    }
+
+    /// Decodes a pool from its key files; without `pk_bytes` the pool can only verify.
+    /// The verifier-only build rejects a proving key.
+    pub fn decode(
+        pk_bytes: Option<&[u8]>,
+        vk_bytes: &[u8],
+        params_bytes: &[u8],
+        policy: DeserializationPolicy,
+    ) -> Result<Self, GachaCircuitError> {
+        match pk_bytes {
+            #[cfg(feature = "prover")]
+            Some(pk_bytes) => {
+                Self::from_keys(GachaKeys::decode(pk_bytes, vk_bytes, params_bytes, policy)?)
+            }
+            #[cfg(not(feature = "prover"))]
+            Some(_) => Err(GachaCircuitError::SetupError(
+                "This build cannot prove; load the pool without a proving key".to_string(),
+            )),
+            None => Self::from_verifier_keys(VerifierKeys::decode(vk_bytes, params_bytes, policy)?),
+        }
+    }
+
+    /// Whether the pool was loaded with a proving key.
+    pub fn can_prove(&self) -> bool {
+        #[cfg(feature = "prover")]
+        {
+            self.proving_key.is_some()
+        }
+        #[cfg(not(feature = "prover"))]
+        {
+            false
+        }
+    }
+
+    /// The proving key; errors if the pool was loaded without one.
+    #[cfg(feature = "prover")]
+    pub fn proving_key(&self) -> Result<&ProvingKey<Bls12_381>, GachaCircuitError> {
+        self.proving_key.as_ref().ok_or_else(|| {
+            GachaCircuitError::SetupError("Pool was loaded without a proving key".to_string())
+        })
+    }
+
+    pub fn verifying_key(&self) -> &PreparedVerifyingKey<Bls12_381> {
+        &self.verifying_key
+    }
+
+    /// SHA-256 of the compressed verifying key, as recorded in the key files and envelopes.
+    pub fn vk_fingerprint(&self) -> &[u8; 32] {
+        &self.vk_fingerprint
+    }
+
+    pub fn poseidon_params(&self) -> &NativePoseidonConfig {
+        &self.poseidon_params
+    }
+
+    /// The pool's Merkle tree has `2^tree_height` leaves.
+    pub fn tree_height(&self) -> usize {
+        self.tree_height
+    }
+
+    /// Proves one pull from this pool.
+    #[cfg(feature = "prover")]
+    pub fn prove<R: RngCore + CryptoRng>(
+        &self,
+        inputs: NativeGachaCircuitInputs,
+        rng: &mut R,
+    ) -> Result<Proof<Bls12_381>, GachaCircuitError> {
+        self.prove_with_progress(inputs, rng, |_| Ok(()))
+    }
+
+    /// Proves one pull from this pool; see [`prover::prove_pull_with_progress`]. The inputs are
+    /// checked with [`NativeGachaCircuitInputs::validate`] first, so a bad witness fails fast.
+    #[cfg(feature = "prover")]
+    pub fn prove_with_progress<R: RngCore + CryptoRng>(
+        &self,
+        inputs: NativeGachaCircuitInputs,
+        rng: &mut R,
+        on_phase: impl FnMut(ProvePhase) -> Result<(), GachaCircuitError>,
+    ) -> Result<Proof<Bls12_381>, GachaCircuitError> {
+        let proving_key = self.proving_key()?;
+        inputs.validate(&self.poseidon_params, self.tree_height)?;
+        prover::prove_pull_with_progress(proving_key, inputs, &self.poseidon_params, rng, on_phase)
+    }
+
+    /// Checks natively that `inputs` lead to their Merkle root in a tree of this pool's height;
+    /// see [`NativeGachaCircuitInputs::verify_merkle_path`].
+    #[cfg(feature = "prover")]
+    pub fn verify_merkle_path(
+        &self,
+        inputs: &NativeGachaCircuitInputs,
+    ) -> Result<bool, GachaCircuitError> {
+        inputs.verify_merkle_path(&self.poseidon_params, self.tree_height)
+    }
+
+    /// Verifies one pull proof against `merkle_root`.
+    pub fn verify(
+        &self,
+        merkle_root: ConstraintField,
+        proof: &Proof<Bls12_381>,
+    ) -> Result<bool, GachaCircuitError> {
+        let public_inputs = prepare_groth16_public_inputs(merkle_root);
+        Groth16::<Bls12_381>::verify_proof(&self.verifying_key, proof, &public_inputs)
+            .map_err(|e| GachaCircuitError::ProofVerification(e.to_string()))
+    }
+
+    /// Verifies an envelope made with this pool's verifying key against its own public inputs.
+    /// Envelopes for another circuit or other keys are rejected with an error.
+    pub fn verify_envelope(
+        &self,
+        envelope: &GachaProofEnvelope,
+    ) -> Result<bool, GachaCircuitError> {
+        envelope.check_user_pull(&self.vk_fingerprint)?;
+        Groth16::<Bls12_381>::verify_proof(
+            &self.verifying_key,
+            &envelope.proof,
+            &envelope.public_inputs,
+        )
+        .map_err(|e| GachaCircuitError::ProofVerification(e.to_string()))
+    }
+
+    /// Batch-verifies `proofs[i]` against `merkle_roots[i]`; see [`batch::verify_gacha_proofs_batch`].
+    pub fn verify_batch<R: RngCore>(
+        &self,
+        merkle_roots: &[ConstraintField],
+        proofs: &[Proof<Bls12_381>],
+        rng: &mut R,
+    ) -> Result<BatchVerificationResult, GachaCircuitError> {
+        batch::verify_gacha_proofs_batch(&self.verifying_key, merkle_roots, proofs, rng)
+    }
+}
+
+/// Loaded pools by id.
+#[derive(Debug, Default)]
+pub struct PoolRegistry {
+    pools: BTreeMap<String, Arc<GachaPool>>,
+}
+
+impl PoolRegistry {
+    pub const fn new() -> Self {
+        Self {
+            pools: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a pool, erroring if `pool_id` is already loaded.
+    pub fn load(&mut self, pool_id: &str, pool: GachaPool) -> Result<(), GachaCircuitError> {
+        if self.pools.contains_key(pool_id) {
+            return Err(GachaCircuitError::SetupError(format!(
+                "Pool '{}' is already loaded",
+                pool_id
+            )));
+        }
+        self.pools.insert(pool_id.to_string(), Arc::new(pool));
+        Ok(())
+    }
+
+    /// Adds a pool or swaps out the one loaded under `pool_id`, returning the old one.
+    pub fn replace(&mut self, pool_id: &str, pool: GachaPool) -> Option<Arc<GachaPool>> {
+        self.pools.insert(pool_id.to_string(), Arc::new(pool))
+    }
+
+    /// Removes a pool, returning it if it was loaded.
+    pub fn unload(&mut self, pool_id: &str) -> Option<Arc<GachaPool>> {
+        self.pools.remove(pool_id)
+    }
+
+    pub fn get(&self, pool_id: &str) -> Result<Arc<GachaPool>, GachaCircuitError> {
+        self.pools
+            .get(pool_id)
+            .cloned()
+            .ok_or_else(|| GachaCircuitError::PoolNotFound(pool_id.to_string()))
+    }
+
+    pub fn contains(&self, pool_id: &str) -> bool {
+        self.pools.contains_key(pool_id)
+    }
+
+    /// Loaded pool ids, sorted.
+    pub fn pool_ids(&self) -> Vec<String> {
+        self.pools.keys().cloned().collect()
+    }
+}
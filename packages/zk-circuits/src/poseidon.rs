@@ -0,0 +1,185 @@
+//! Poseidon parameters for the BLS12-381 scalar field, derived for a target security level.
+//!
+//! Round numbers follow the Poseidon paper (Grassi et al., ePrint 2019/458, Section 5.5 and the
+//! reference `calc_round_numbers.py`): the cheapest `(R_F, R_P)` that resists the statistical,
+//! interpolation and Gröbner-basis attacks (including the 2023/537 bound) is searched for, then
+//! the recommended margin of `R_F + 2` and `R_P · 1.075` is added. Round constants and the MDS
+//! matrix come from the paper's Grain LFSR via arkworks.
+//!
+//! This is the only place the crate, the scripts and the tests get Poseidon parameters from.
+
+use ark_crypto_primitives::sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig};
+use ark_ff::PrimeField;
+use ark_std::{format, string::ToString};
+
+use crate::error::GachaCircuitError;
+use crate::types::{ConstraintField, NativePoseidonConfig};
+
+pub const DEFAULT_SECURITY_LEVEL: u32 = 128;
+pub const DEFAULT_ALPHA: u64 = 17;
+pub const DEFAULT_RATE: usize = 2;
+pub const CAPACITY: usize = 1;
+
+/// Full and partial round counts, security margin included.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RoundNumbers {
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+}
+
+/// Derives round numbers for an `x^alpha` Poseidon permutation of `width` field elements.
+pub fn round_numbers(
+    security_level: u32,
+    width: usize,
+    alpha: u64,
+) -> Result<RoundNumbers, GachaCircuitError> {
+    if width < 2 {
+        return Err(GachaCircuitError::InvalidInput(
+            "Poseidon width must be at least 2".to_string(),
+        ));
+    }
+    if security_level == 0 || security_level > ConstraintField::MODULUS_BIT_SIZE {
+        return Err(GachaCircuitError::InvalidInput(format!(
+            "Security level must be between 1 and {} bits",
+            ConstraintField::MODULUS_BIT_SIZE
+        )));
+    }
+    if alpha < 3 || gcd(alpha, modulus_minus_one_mod(alpha)) != 1 {
+        return Err(GachaCircuitError::InvalidInput(format!(
+            "x^{} is not a permutation of the scalar field",
+            alpha
+        )));
+    }
+
+    // Same search order and tie-breaking as the reference script
+    let mut best: Option<(usize, RoundNumbers)> = None;
+    for partial in 1..500 {
+        for full in (4..100).step_by(2) {
+            if !is_secure(security_level, width, full, partial, alpha) {
+                continue;
+            }
+            let rounds = RoundNumbers {
+                full_rounds: full + 2,
+                partial_rounds: (partial as f64 * 1.075).ceil() as usize,
+            };
+            let cost = width * rounds.full_rounds + rounds.partial_rounds;
+            let is_better = best.is_none_or(|(best_cost, best_rounds)| {
+                cost < best_cost
+                    || (cost == best_cost && rounds.full_rounds < best_rounds.full_rounds)
+            });
+            if is_better {
+                best = Some((cost, rounds));
+            }
+        }
+    }
+
+    best.map(|(_, rounds)| rounds).ok_or_else(|| {
+        GachaCircuitError::InvalidInput(format!(
+            "No Poseidon round numbers reach {}-bit security",
+            security_level
+        ))
+    })
+}
+
+/// Builds a Poseidon sponge configuration with `rate` and capacity [`CAPACITY`].
+pub fn poseidon_config(
+    security_level: u32,
+    rate: usize,
+    alpha: u64,
+) -> Result<NativePoseidonConfig, GachaCircuitError> {
+    let width = rate + CAPACITY;
+    let rounds = round_numbers(security_level, width, alpha)?;
+    // arkworks takes the rate and instantiates the Grain LFSR for `rate + 1` cells
+    let (ark, mds) = find_poseidon_ark_and_mds::<ConstraintField>(
+        ConstraintField::MODULUS_BIT_SIZE as u64,
+        rate,
+        rounds.full_rounds as u64,
+        rounds.partial_rounds as u64,
+        0,
+    );
+    Ok(PoseidonConfig {
+        full_rounds: rounds.full_rounds,
+        partial_rounds: rounds.partial_rounds,
+        alpha,
+        ark,
+        mds,
+        rate,
+        capacity: CAPACITY,
+    })
+}
+
+/// The parameters the gacha circuit uses: 128-bit security, rate 2, `x^17`.
+pub fn default_poseidon_config() -> NativePoseidonConfig {
+    poseidon_config(DEFAULT_SECURITY_LEVEL, DEFAULT_RATE, DEFAULT_ALPHA)
+        .expect("default Poseidon parameters are valid")
+}
+
+/// The attack bounds from the reference script, for an `R_F`/`R_P` candidate without margin.
+fn is_secure(security_level: u32, width: usize, full: usize, partial: usize, alpha: u64) -> bool {
+    let m = security_level as f64;
+    let t = width as f64;
+    let r_f = full as f64;
+    let r_p = partial as f64;
+    let a = alpha as f64;
+    let n = ConstraintField::MODULUS_BIT_SIZE as f64;
+    let log2_p = log2_modulus();
+    let log_alpha = |x: f64| x.ln() / a.ln();
+
+    // Statistical attacks
+    let r_f_1 = if m <= (log2_p - (a - 1.0) / 2.0).floor() * (t + 1.0) {
+        6.0
+    } else {
+        10.0
+    };
+    // Interpolation attack
+    let r_f_2 = 1.0 + (log_alpha(2.0) * m.min(n)).ceil() + log_alpha(t).ceil() - r_p;
+    // Gröbner basis attacks
+    let r_f_3 = log_alpha(2.0) * m.min(log2_p) - r_p;
+    let r_f_4 = t - 1.0 + log_alpha(2.0) * (m / (t + 1.0)).min(log2_p / 2.0) - r_p;
+    let r_f_5 = (t - 2.0 + m / (2.0 * a.log2()) - r_p) / (t - 1.0);
+    let r_f_min = [r_f_1, r_f_2, r_f_3, r_f_4, r_f_5]
+        .into_iter()
+        .map(f64::ceil)
+        .fold(f64::MIN, f64::max);
+    if r_f < r_f_min {
+        return false;
+    }
+
+    // Gröbner basis bound from ePrint 2023/537
+    let r = (t / 3.0).floor();
+    let over = (r_f - 1.0) * t + r_p + r + r * (r_f / 2.0) + r_p + a;
+    let under = r * (r_f / 2.0) + r_p + a;
+    (2.0 * log2_binomial(over as u64, under as u64)).ceil() >= m
+}
+
+/// `log2(n choose k)`.
+pub(crate) fn log2_binomial(n: u64, k: u64) -> f64 {
+    let k = k.min(n - k);
+    (1..=k)
+        .map(|i| ((n - k + i) as f64 / i as f64).log2())
+        .sum()
+}
+
+fn log2_modulus() -> f64 {
+    let limbs = ConstraintField::MODULUS.0;
+    let top = limbs.len() - 1;
+    let leading = limbs[top] as f64 + limbs[top - 1] as f64 / 2f64.powi(64);
+    leading.log2() + (64 * top) as f64
+}
+
+/// `(p - 1) mod m` for the scalar field modulus `p`.
+fn modulus_minus_one_mod(m: u64) -> u64 {
+    let remainder = ConstraintField::MODULUS
+        .0
+        .iter()
+        .rev()
+        .fold(0u128, |acc, &limb| ((acc << 64) | limb as u128) % m as u128);
+    ((remainder + m as u128 - 1) % m as u128) as u64
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
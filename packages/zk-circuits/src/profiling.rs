@@ -0,0 +1,145 @@
+//! Constraint and witness profiling for the crate's circuits.
+//!
+//! [`profile_circuits`] synthesizes every circuit for a tree height in setup mode (no witness
+//! values needed) and reports its constraint and variable counts. Circuits wrap their main
+//! steps in [`namespace`], which records how many constraints and private variables each step
+//! adds while a profile is being taken; outside of profiling it just runs the step.
+//!
+//! `tests/constraint_budget.json` pins the counts; `cargo run --bin profile_circuits` in
+//! `scripts/` prints them.
+
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError, SynthesisMode,
+};
+use ark_std::{cell::RefCell, string::String, vec::Vec};
+use serde::Serialize;
+
+use crate::circuit::{QuaternaryUserPullCircuit, UserPullCircuit};
+use crate::error::GachaCircuitError;
+use crate::hash::{MerkleHash, PoseidonHash};
+use crate::quaternary::{quaternary_depth, QuaternaryMerkleParams};
+use crate::types::{ConstraintField, NativeGachaCircuitInputs, QuaternaryGachaCircuitInputs};
+
+/// Counts added by one [`namespace`], nested names joined with `/`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct NamespaceProfile {
+    pub name: String,
+    pub constraints: usize,
+    pub private_variables: usize,
+}
+
+/// Size of one circuit at one tree height.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircuitProfile {
+    pub circuit: String,
+    pub tree_height: usize,
+    pub constraints: usize,
+    /// Public inputs, excluding the constant `1`
+    pub public_inputs: usize,
+    pub private_variables: usize,
+    pub namespaces: Vec<NamespaceProfile>,
+}
+
+#[derive(Default)]
+struct Recorder {
+    stack: Vec<&'static str>,
+    namespaces: Vec<NamespaceProfile>,
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<Recorder>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` as the named step of a circuit, recording its counts if a profile is being taken.
+pub(crate) fn namespace<T>(
+    cs: &ConstraintSystemRef<ConstraintField>,
+    name: &'static str,
+    f: impl FnOnce() -> Result<T, SynthesisError>,
+) -> Result<T, SynthesisError> {
+    let profiling = RECORDER.with(|recorder| match recorder.borrow_mut().as_mut() {
+        Some(recorder) => {
+            recorder.stack.push(name);
+            true
+        }
+        None => false,
+    });
+    if !profiling {
+        return f();
+    }
+
+    let (constraints, private_variables) = (cs.num_constraints(), cs.num_witness_variables());
+    let result = f();
+    RECORDER.with(|recorder| {
+        if let Some(recorder) = recorder.borrow_mut().as_mut() {
+            let name = recorder.stack.join("/");
+            recorder.stack.pop();
+            let constraints = cs.num_constraints() - constraints;
+            let private_variables = cs.num_witness_variables() - private_variables;
+            // The same step may run more than once; its counts are summed
+            match recorder.namespaces.iter_mut().find(|ns| ns.name == name) {
+                Some(ns) => {
+                    ns.constraints += constraints;
+                    ns.private_variables += private_variables;
+                }
+                None => recorder.namespaces.push(NamespaceProfile {
+                    name,
+                    constraints,
+                    private_variables,
+                }),
+            }
+        }
+    });
+    result
+}
+
+/// Synthesizes `circuit` in setup mode and reports its size.
+pub fn profile_circuit<C: ConstraintSynthesizer<ConstraintField>>(
+    name: &str,
+    tree_height: usize,
+    circuit: C,
+) -> Result<CircuitProfile, GachaCircuitError> {
+    let cs = ConstraintSystem::<ConstraintField>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+
+    RECORDER.with(|recorder| *recorder.borrow_mut() = Some(Recorder::default()));
+    let result = circuit.generate_constraints(cs.clone());
+    let recorder = RECORDER
+        .with(|recorder| recorder.borrow_mut().take())
+        .unwrap_or_default();
+    result?;
+
+    Ok(CircuitProfile {
+        circuit: name.into(),
+        tree_height,
+        constraints: cs.num_constraints(),
+        public_inputs: cs.num_instance_variables() - 1,
+        private_variables: cs.num_witness_variables(),
+        namespaces: recorder.namespaces,
+    })
+}
+
+/// Profiles every circuit in the crate for a tree of `2^tree_height` leaves.
+/// `QuaternaryUserPullCircuit` is only included for even heights.
+pub fn profile_circuits(tree_height: usize) -> Result<Vec<CircuitProfile>, GachaCircuitError> {
+    let poseidon_params = PoseidonHash::default_parameters();
+    let mut profiles = vec![profile_circuit(
+        "UserPullCircuit",
+        tree_height,
+        UserPullCircuit::<PoseidonHash>::new(
+            NativeGachaCircuitInputs::placeholder(tree_height),
+            poseidon_params.clone(),
+        ),
+    )?];
+    if let Ok(depth) = quaternary_depth(tree_height) {
+        profiles.push(profile_circuit(
+            "QuaternaryUserPullCircuit",
+            tree_height,
+            QuaternaryUserPullCircuit::new(
+                QuaternaryGachaCircuitInputs::placeholder(depth),
+                QuaternaryMerkleParams::new(poseidon_params),
+            ),
+        )?);
+    }
+    Ok(profiles)
+}
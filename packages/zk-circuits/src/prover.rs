@@ -0,0 +1,229 @@
+//! Native Groth16 proving for `UserPullCircuit`.
+//!
+//! With the `parallel` feature, arkworks runs the prover's MSMs and FFTs on rayon's thread pool
+//! and [`prove_pulls`] also proves independent pulls concurrently. Without it everything runs
+//! on the calling thread, which is what the wasm build uses.
+//!
+//! [`prove_pull_with_progress`] is the Groth16 prover split into [`ProvePhase`]s, reporting each
+//! phase before it starts so callers can show progress or cancel between phases. It draws the
+//! same randomness as `Groth16::prove` and produces the same proof.
+
+use ark_bls12_381::{Bls12_381, G1Projective};
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
+use ark_ff::{PrimeField, UniformRand, Zero};
+use ark_groth16::{
+    r1cs_to_qap::{LibsnarkReduction, R1CSToQAP},
+    Proof, ProvingKey,
+};
+use ark_poly::GeneralEvaluationDomain;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, OptimizationGoal};
+use ark_std::{
+    cfg_into_iter, format,
+    rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng},
+    string::ToString,
+    vec::Vec,
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::circuit::UserPullCircuit;
+use crate::error::GachaCircuitError;
+use crate::hash::PoseidonHash;
+use crate::types::{ConstraintField, NativeGachaCircuitInputs, NativePoseidonConfig};
+
+/// The steps of proof generation, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProvePhase {
+    /// Circuit synthesis: witness generation and constraint inlining
+    Synthesis,
+    /// R1CS-to-QAP witness map (FFTs) giving the coefficients of `H`
+    WitnessMap,
+    /// MSM over the `A` query (G1)
+    MsmA,
+    /// MSMs over the `B` queries (G1 and G2)
+    MsmB,
+    /// MSMs over the `H` and `L` queries, finishing `C` (G1)
+    MsmC,
+    /// Proof serialization; reported by callers that serialize the proof
+    Serialization,
+}
+
+impl ProvePhase {
+    pub const ALL: [ProvePhase; 6] = [
+        ProvePhase::Synthesis,
+        ProvePhase::WitnessMap,
+        ProvePhase::MsmA,
+        ProvePhase::MsmB,
+        ProvePhase::MsmC,
+        ProvePhase::Serialization,
+    ];
+
+    /// Position in [`ProvePhase::ALL`].
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProvePhase::Synthesis => "synthesis",
+            ProvePhase::WitnessMap => "witness_map",
+            ProvePhase::MsmA => "msm_a",
+            ProvePhase::MsmB => "msm_b",
+            ProvePhase::MsmC => "msm_c",
+            ProvePhase::Serialization => "serialization",
+        }
+    }
+}
+
+/// Threads available to the prover: rayon's pool size with `parallel`, otherwise 1.
+pub fn num_threads() -> usize {
+    #[cfg(feature = "parallel")]
+    {
+        rayon::current_num_threads()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        1
+    }
+}
+
+/// Proves one pull.
+pub fn prove_pull<R: RngCore + CryptoRng>(
+    proving_key: &ProvingKey<Bls12_381>,
+    inputs: NativeGachaCircuitInputs,
+    poseidon_params: &NativePoseidonConfig,
+    rng: &mut R,
+) -> Result<Proof<Bls12_381>, GachaCircuitError> {
+    prove_pull_with_progress(proving_key, inputs, poseidon_params, rng, |_| Ok(()))
+}
+
+/// Proves one pull, calling `on_phase` before each phase up to [`ProvePhase::MsmC`].
+/// An error from `on_phase` (e.g. [`GachaCircuitError::Cancelled`]) stops proving and is returned.
+///
+/// The inputs are checked with [`NativeGachaCircuitInputs::validate`] for the tree height their
+/// path implies, and the synthesized circuit must match the size of `proving_key`; either
+/// failing is [`GachaCircuitError::InvalidInput`] rather than a proof that does not verify.
+pub fn prove_pull_with_progress<R: RngCore + CryptoRng>(
+    proving_key: &ProvingKey<Bls12_381>,
+    inputs: NativeGachaCircuitInputs,
+    poseidon_params: &NativePoseidonConfig,
+    rng: &mut R,
+    mut on_phase: impl FnMut(ProvePhase) -> Result<(), GachaCircuitError>,
+) -> Result<Proof<Bls12_381>, GachaCircuitError> {
+    let tree_height = inputs.native_merkle_path.auth_path.len() + 1;
+    inputs.validate(poseidon_params, tree_height)?;
+
+    let pk = proving_key;
+    let proof_generation =
+        |e: ark_relations::r1cs::SynthesisError| GachaCircuitError::ProofGeneration(e.to_string());
+    // Same draws, in the same order, as `Groth16::prove`
+    let r = ConstraintField::rand(rng);
+    let s = ConstraintField::rand(rng);
+
+    // 1. Synthesize the witness
+    on_phase(ProvePhase::Synthesis)?;
+    let cs = ConstraintSystem::<ConstraintField>::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Constraints);
+    UserPullCircuit::<PoseidonHash>::new(inputs, poseidon_params.clone())
+        .generate_constraints(cs.clone())
+        .map_err(proof_generation)?;
+    debug_assert!(cs.is_satisfied().unwrap());
+    cs.finalize();
+    if cs.num_instance_variables() + cs.num_witness_variables() != pk.a_query.len() {
+        return Err(GachaCircuitError::InvalidInput(format!(
+            "Inputs are for a tree of height {}, which the proving key was not generated for",
+            tree_height
+        )));
+    }
+
+    // 2. Reduce to the QAP witness
+    on_phase(ProvePhase::WitnessMap)?;
+    let h = LibsnarkReduction::witness_map::<
+        ConstraintField,
+        GeneralEvaluationDomain<ConstraintField>,
+    >(cs.clone())
+    .map_err(proof_generation)?;
+    let assignment: Vec<_> = {
+        let cs = cs.borrow().ok_or_else(|| {
+            GachaCircuitError::ProofGeneration("Constraint system is not available".to_string())
+        })?;
+        cs.instance_assignment[1..]
+            .iter()
+            .chain(&cs.witness_assignment)
+            .map(|x| x.into_bigint())
+            .collect()
+    };
+    let aux_assignment = &assignment[cs.num_instance_variables() - 1..];
+
+    // 3. A = alpha + sum a_i(x) + r delta
+    on_phase(ProvePhase::MsmA)?;
+    let g_a = calculate_coeff(pk.delta_g1 * r, &pk.a_query, pk.vk.alpha_g1, &assignment);
+
+    // 4. B = beta + sum b_i(x) + s delta, in G1 (only needed when r != 0) and G2
+    on_phase(ProvePhase::MsmB)?;
+    let g1_b = if r.is_zero() {
+        G1Projective::zero()
+    } else {
+        calculate_coeff(pk.delta_g1 * s, &pk.b_g1_query, pk.beta_g1, &assignment)
+    };
+    let g2_b = calculate_coeff(
+        pk.vk.delta_g2 * s,
+        &pk.b_g2_query,
+        pk.vk.beta_g2,
+        &assignment,
+    );
+
+    // 5. C = sA + rB - rs delta + sum l_i(x) + h(x) t(x) / delta
+    on_phase(ProvePhase::MsmC)?;
+    let h_assignment: Vec<_> = cfg_into_iter!(h).map(|x| x.into_bigint()).collect();
+    let h_acc = G1Projective::msm_bigint(&pk.h_query, &h_assignment);
+    let l_aux_acc = G1Projective::msm_bigint(&pk.l_query, aux_assignment);
+    let g_c = g_a * s + g1_b * r - pk.delta_g1 * (r * s) + l_aux_acc + h_acc;
+
+    Ok(Proof {
+        a: g_a.into_affine(),
+        b: g2_b.into_affine(),
+        c: g_c.into_affine(),
+    })
+}
+
+/// `initial + query[0] + sum_i assignment_i query[i + 1] + vk_param`
+fn calculate_coeff<G: AffineRepr>(
+    initial: G::Group,
+    query: &[G],
+    vk_param: G,
+    assignment: &[<G::ScalarField as PrimeField>::BigInt],
+) -> G::Group
+where
+    G::Group: VariableBaseMSM<MulBase = G>,
+{
+    initial + query[0] + G::Group::msm_bigint(&query[1..], assignment) + vk_param
+}
+
+/// Proves many pulls, concurrently with the `parallel` feature. Each proof gets its own RNG
+/// seeded from `rng`, so the output does not depend on scheduling.
+pub fn prove_pulls<R: RngCore + CryptoRng>(
+    proving_key: &ProvingKey<Bls12_381>,
+    inputs: Vec<NativeGachaCircuitInputs>,
+    poseidon_params: &NativePoseidonConfig,
+    rng: &mut R,
+) -> Result<Vec<Proof<Bls12_381>>, GachaCircuitError> {
+    let jobs: Vec<_> = inputs
+        .into_iter()
+        .map(|inputs| {
+            let mut seed = [0u8; 32];
+            rng.fill_bytes(&mut seed);
+            (inputs, seed)
+        })
+        .collect();
+    cfg_into_iter!(jobs)
+        .map(|(inputs, seed)| {
+            prove_pull(
+                proving_key,
+                inputs,
+                poseidon_params,
+                &mut StdRng::from_seed(seed),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,92 @@
+use ark_crypto_primitives::crh::{
+    poseidon::constraints::{CRHGadget, CRHParametersVar},
+    CRHSchemeGadget,
+};
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    boolean::Boolean,
+    eq::EqGadget,
+    fields::fp::FpVar,
+};
+use ark_relations::r1cs::{Namespace, SynthesisError};
+use ark_std::{borrow::Borrow, vec::Vec};
+
+use super::tree::{QuaternaryMerklePath, ARITY};
+use crate::types::ConstraintField;
+
+/// In-circuit [`QuaternaryMerklePath`]: two position bits and three siblings per level.
+#[derive(Clone)]
+pub struct QuaternaryMerklePathVar {
+    /// Per level, the child position `low + 2 * high` as `[low, high]`
+    positions: Vec<[Boolean<ConstraintField>; 2]>,
+    siblings: Vec<[FpVar<ConstraintField>; ARITY - 1]>,
+}
+
+impl QuaternaryMerklePathVar {
+    /// Hashes `leaf_digest` up the path with the width-5 node parameters.
+    pub fn compute_root(
+        &self,
+        node_params: &CRHParametersVar<ConstraintField>,
+        leaf_digest: &FpVar<ConstraintField>,
+    ) -> Result<FpVar<ConstraintField>, SynthesisError> {
+        self.positions.iter().zip(&self.siblings).try_fold(
+            leaf_digest.clone(),
+            |node, ([low, high], [s0, s1, s2])| {
+                // Place `node` at position `low + 2 * high` and the siblings around it, in order
+                let children = [
+                    (low | high).select(s0, &node)?,
+                    high.select(s1, &low.select(&node, s0)?)?,
+                    high.select(&low.select(s2, &node)?, s1)?,
+                    (low & high).select(&node, s2)?,
+                ];
+                CRHGadget::<ConstraintField>::evaluate(node_params, &children)
+            },
+        )
+    }
+
+    /// Returns whether `leaf` (before hashing) sits at the path's position under `root`.
+    pub fn verify_membership(
+        &self,
+        leaf_params: &CRHParametersVar<ConstraintField>,
+        node_params: &CRHParametersVar<ConstraintField>,
+        root: &FpVar<ConstraintField>,
+        leaf: &[FpVar<ConstraintField>],
+    ) -> Result<Boolean<ConstraintField>, SynthesisError> {
+        let leaf_digest = CRHGadget::<ConstraintField>::evaluate(leaf_params, leaf)?;
+        self.compute_root(node_params, &leaf_digest)?.is_eq(root)
+    }
+}
+
+impl AllocVar<QuaternaryMerklePath, ConstraintField> for QuaternaryMerklePathVar {
+    fn new_variable<T: Borrow<QuaternaryMerklePath>>(
+        cs: impl Into<Namespace<ConstraintField>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        let path = f()?;
+        let path = path.borrow();
+
+        let mut positions = Vec::with_capacity(path.siblings.len());
+        let mut siblings = Vec::with_capacity(path.siblings.len());
+        for (level, native_siblings) in path.siblings.iter().enumerate() {
+            let position = path.leaf_index >> (2 * level);
+            let bit = |shift: usize| {
+                Boolean::new_variable(cs.clone(), || Ok((position >> shift) & 1 == 1), mode)
+            };
+            positions.push([bit(0)?, bit(1)?]);
+
+            let [s0, s1, s2] = native_siblings;
+            siblings.push([
+                FpVar::new_variable(cs.clone(), || Ok(*s0), mode)?,
+                FpVar::new_variable(cs.clone(), || Ok(*s1), mode)?,
+                FpVar::new_variable(cs.clone(), || Ok(*s2), mode)?,
+            ]);
+        }
+        Ok(Self {
+            positions,
+            siblings,
+        })
+    }
+}
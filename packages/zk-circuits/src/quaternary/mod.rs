@@ -0,0 +1,19 @@
+//! Quaternary (4-ary) Merkle tree over Poseidon, for pools where path constraints dominate.
+//!
+//! Leaves are hashed exactly as in the binary tree (`Poseidon([secret_key, item_id])` with the
+//! deployed parameters), so leaf digests are shared between both layouts. Inner nodes hash their
+//! four children in one width-5 Poseidon permutation (rate 4), which replaces two binary levels
+//! and their two width-3 permutations. A tree of depth `d` has `4^d` leaves, i.e. the same pool
+//! as a binary tree of height `2d`.
+//!
+//! [`QuaternaryMerkleTree`] and [`QuaternaryMerklePath`] are the native builder and path;
+//! [`QuaternaryMerklePathVar`] is the gadget used by `QuaternaryUserPullCircuit`.
+
+mod constraints;
+mod tree;
+
+pub use constraints::QuaternaryMerklePathVar;
+pub use tree::{
+    node_poseidon_config, quaternary_depth, QuaternaryMerkleParams, QuaternaryMerklePath,
+    QuaternaryMerkleTree, ARITY,
+};
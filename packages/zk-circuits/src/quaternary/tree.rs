@@ -0,0 +1,234 @@
+use ark_crypto_primitives::crh::{poseidon::CRH as PoseidonCRH, CRHScheme};
+use ark_ff::Zero;
+use ark_std::{format, string::ToString, vec::Vec};
+
+use crate::error::GachaCircuitError;
+use crate::poseidon::{
+    default_poseidon_config, poseidon_config, DEFAULT_ALPHA, DEFAULT_SECURITY_LEVEL,
+};
+use crate::types::{ConstraintField, NativePoseidonConfig};
+
+/// Children per inner node.
+pub const ARITY: usize = 4;
+
+/// Poseidon parameters for inner nodes: rate [`ARITY`], so one permutation per node.
+pub fn node_poseidon_config() -> NativePoseidonConfig {
+    poseidon_config(DEFAULT_SECURITY_LEVEL, ARITY, DEFAULT_ALPHA)
+        .expect("quaternary node Poseidon parameters are valid")
+}
+
+/// Depth of the quaternary tree holding `2^tree_height` leaves.
+pub fn quaternary_depth(tree_height: usize) -> Result<usize, GachaCircuitError> {
+    if !tree_height.is_multiple_of(2) {
+        return Err(GachaCircuitError::InvalidInput(format!(
+            "A quaternary tree needs an even tree height, got {}",
+            tree_height
+        )));
+    }
+    Ok(tree_height / 2)
+}
+
+/// Leaf and inner-node hash parameters of a quaternary tree.
+#[derive(Clone, Debug)]
+pub struct QuaternaryMerkleParams {
+    /// Width-3 parameters hashing `[secret_key, item_id]`, as in the binary tree
+    pub leaf_params: NativePoseidonConfig,
+    /// Width-5 parameters hashing four children
+    pub node_params: NativePoseidonConfig,
+}
+
+impl QuaternaryMerkleParams {
+    /// Uses `leaf_params` for leaves (e.g. loaded from `params.bin`) and [`node_poseidon_config`]
+    /// for inner nodes.
+    pub fn new(leaf_params: NativePoseidonConfig) -> Self {
+        Self {
+            leaf_params,
+            node_params: node_poseidon_config(),
+        }
+    }
+
+    pub fn hash_leaf(
+        &self,
+        leaf: &[ConstraintField],
+    ) -> Result<ConstraintField, GachaCircuitError> {
+        Ok(PoseidonCRH::<ConstraintField>::evaluate(
+            &self.leaf_params,
+            leaf,
+        )?)
+    }
+
+    pub fn hash_node(
+        &self,
+        children: &[ConstraintField; ARITY],
+    ) -> Result<ConstraintField, GachaCircuitError> {
+        Ok(PoseidonCRH::<ConstraintField>::evaluate(
+            &self.node_params,
+            children.as_slice(),
+        )?)
+    }
+}
+
+impl Default for QuaternaryMerkleParams {
+    fn default() -> Self {
+        Self::new(default_poseidon_config())
+    }
+}
+
+/// A native quaternary Merkle tree storing every level of digests.
+#[derive(Clone, Debug)]
+pub struct QuaternaryMerkleTree {
+    /// `levels[0]` holds the leaf digests, the last level holds only the root
+    levels: Vec<Vec<ConstraintField>>,
+}
+
+impl QuaternaryMerkleTree {
+    /// Builds the tree from raw leaves, each hashed with the leaf parameters.
+    pub fn new<L: AsRef<[ConstraintField]>>(
+        params: &QuaternaryMerkleParams,
+        leaves: &[L],
+    ) -> Result<Self, GachaCircuitError> {
+        let digests = leaves
+            .iter()
+            .map(|leaf| params.hash_leaf(leaf.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new_with_leaf_digests(params, digests)
+    }
+
+    /// Builds the tree from pre-hashed leaves; their number must be a power of four.
+    pub fn new_with_leaf_digests(
+        params: &QuaternaryMerkleParams,
+        leaf_digests: Vec<ConstraintField>,
+    ) -> Result<Self, GachaCircuitError> {
+        let num_leaves = leaf_digests.len();
+        if !num_leaves.is_power_of_two() || !num_leaves.trailing_zeros().is_multiple_of(2) {
+            return Err(GachaCircuitError::InvalidInput(format!(
+                "A quaternary tree needs a power-of-four number of leaves, got {}",
+                num_leaves
+            )));
+        }
+
+        let mut levels = vec![leaf_digests];
+        while levels.last().map_or(0, Vec::len) > 1 {
+            let parents = levels
+                .last()
+                .expect("at least the leaf level")
+                .chunks_exact(ARITY)
+                .map(|children| params.hash_node(children.try_into().expect("chunks of ARITY")))
+                .collect::<Result<Vec<_>, _>>()?;
+            levels.push(parents);
+        }
+        Ok(Self { levels })
+    }
+
+    pub fn root(&self) -> ConstraintField {
+        self.levels.last().expect("at least the leaf level")[0]
+    }
+
+    /// Number of inner levels; the tree has `4^depth` leaves.
+    pub fn depth(&self) -> usize {
+        self.levels.len() - 1
+    }
+
+    pub fn num_leaves(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    /// The three siblings of the leaf at `index` on every level, leaf level first.
+    pub fn generate_proof(&self, index: usize) -> Result<QuaternaryMerklePath, GachaCircuitError> {
+        if index >= self.num_leaves() {
+            return Err(GachaCircuitError::InvalidInput(format!(
+                "Leaf index {} out of range for {} leaves",
+                index,
+                self.num_leaves()
+            )));
+        }
+
+        let mut position = index;
+        let siblings = self.levels[..self.depth()]
+            .iter()
+            .map(|level| {
+                let first = position - position % ARITY;
+                let node_siblings = sibling_slots(position % ARITY).map(|slot| level[first + slot]);
+                position /= ARITY;
+                node_siblings
+            })
+            .collect();
+        Ok(QuaternaryMerklePath {
+            leaf_index: index,
+            siblings,
+        })
+    }
+}
+
+/// Authentication path of a quaternary tree.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuaternaryMerklePath {
+    pub leaf_index: usize,
+    /// Per level, leaf level first: the other three children, in order
+    pub siblings: Vec<[ConstraintField; ARITY - 1]>,
+}
+
+impl QuaternaryMerklePath {
+    /// An all-zero path of `depth` levels. Only the shape matters, e.g. for key generation.
+    pub fn placeholder(depth: usize) -> Self {
+        Self {
+            leaf_index: 0,
+            siblings: vec![[ConstraintField::zero(); ARITY - 1]; depth],
+        }
+    }
+
+    /// Recomputes the root from a leaf digest.
+    pub fn compute_root(
+        &self,
+        params: &QuaternaryMerkleParams,
+        leaf_digest: ConstraintField,
+    ) -> Result<ConstraintField, GachaCircuitError> {
+        if self.leaf_index >> (2 * self.siblings.len()) != 0 {
+            return Err(GachaCircuitError::InvalidInput(
+                "Leaf index does not fit the path length".to_string(),
+            ));
+        }
+
+        let mut position = self.leaf_index;
+        self.siblings
+            .iter()
+            .try_fold(leaf_digest, |node, siblings| {
+                let children = insert_at(node, siblings, position % ARITY);
+                position /= ARITY;
+                params.hash_node(&children)
+            })
+    }
+
+    /// Checks that `leaf` (before hashing) sits at `leaf_index` under `root`.
+    pub fn verify(
+        &self,
+        params: &QuaternaryMerkleParams,
+        root: &ConstraintField,
+        leaf: &[ConstraintField],
+    ) -> Result<bool, GachaCircuitError> {
+        let leaf_digest = params.hash_leaf(leaf)?;
+        Ok(self.compute_root(params, leaf_digest)? == *root)
+    }
+}
+
+/// Slots of the three siblings of the child at `position`.
+fn sibling_slots(position: usize) -> [usize; ARITY - 1] {
+    let mut slots = [0; ARITY - 1];
+    for (slot, index) in slots.iter_mut().zip((0..ARITY).filter(|&i| i != position)) {
+        *slot = index;
+    }
+    slots
+}
+
+/// The four children with `node` at `position` and `siblings` around it.
+fn insert_at(
+    node: ConstraintField,
+    siblings: &[ConstraintField; ARITY - 1],
+    position: usize,
+) -> [ConstraintField; ARITY] {
+    let mut children = [node; ARITY];
+    for (slot, sibling) in sibling_slots(position).into_iter().zip(siblings) {
+        children[slot] = *sibling;
+    }
+    children
+}
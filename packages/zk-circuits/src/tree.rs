@@ -0,0 +1,144 @@
+//! Building a pool's Merkle tree: the leaves `[secret_key, item_id]`, the root published with the
+//! keys, and the item file each pull hands to the prover.
+//!
+//! [`GachaTree`] is what `prepare_gacha_data` builds; `api::GachaTreeBuilder` (`wasm` feature)
+//! exposes it to JS so an operator console can preview a pool in the browser. Trees always have
+//! the `2^tree_height` leaves the keys were generated for; positions without an item get a random
+//! pair.
+
+use ark_crypto_primitives::merkle_tree::MerkleTree;
+use ark_ff::UniformRand;
+use ark_std::{
+    format,
+    rand::{CryptoRng, RngCore},
+    vec::Vec,
+};
+
+use crate::error::GachaCircuitError;
+use crate::hash::PoseidonHash;
+use crate::types::{
+    fr_to_hex, leaf_digest, ConstraintField, FieldEncoding, GachaMerkleConfig,
+    NativeGachaCircuitInputs, NativePoseidonConfig, WasmMerkleLeafInputs,
+};
+
+pub use crate::types::MAX_TREE_HEIGHT;
+
+/// One leaf of the gacha tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GachaLeaf {
+    pub secret_key: ConstraintField,
+    pub item_id: ConstraintField,
+}
+
+impl GachaLeaf {
+    /// A leaf with a random secret key and item id, e.g. to fill positions without an item.
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self {
+            secret_key: ConstraintField::rand(rng),
+            item_id: ConstraintField::rand(rng),
+        }
+    }
+}
+
+/// A binary gacha Merkle tree with the leaves it was built from.
+pub struct GachaTree {
+    tree: MerkleTree<GachaMerkleConfig>,
+    leaves: Vec<GachaLeaf>,
+    tree_height: usize,
+}
+
+impl GachaTree {
+    /// Builds a tree of `2^tree_height` leaves, `leaves` first and random pairs after them.
+    /// Filler leaves are random rather than fixed so that nobody knows a secret key for them.
+    pub fn build<R: RngCore + CryptoRng>(
+        params: &NativePoseidonConfig,
+        tree_height: usize,
+        mut leaves: Vec<GachaLeaf>,
+        rng: &mut R,
+    ) -> Result<Self, GachaCircuitError> {
+        if tree_height == 0 || tree_height > MAX_TREE_HEIGHT {
+            return Err(GachaCircuitError::InvalidInput(format!(
+                "Tree height must be between 1 and {}, got {}",
+                MAX_TREE_HEIGHT, tree_height
+            )));
+        }
+        let num_leaves = 1usize << tree_height;
+        if leaves.len() > num_leaves {
+            return Err(GachaCircuitError::InvalidInput(format!(
+                "{} leaves do not fit in a tree of height {} ({} leaves)",
+                leaves.len(),
+                tree_height,
+                num_leaves
+            )));
+        }
+        leaves.resize_with(num_leaves, || GachaLeaf::random(rng));
+
+        let leaf_digests = leaves
+            .iter()
+            .map(|leaf| leaf_digest::<PoseidonHash>(params, leaf.secret_key, leaf.item_id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let tree =
+            MerkleTree::<GachaMerkleConfig>::new_with_leaf_digest(params, params, leaf_digests)?;
+        Ok(Self {
+            tree,
+            leaves,
+            tree_height,
+        })
+    }
+
+    pub fn root(&self) -> ConstraintField {
+        self.tree.root()
+    }
+
+    /// The tree has `2^tree_height` leaves.
+    pub fn tree_height(&self) -> usize {
+        self.tree_height
+    }
+
+    /// All leaves, filler included.
+    pub fn leaves(&self) -> &[GachaLeaf] {
+        &self.leaves
+    }
+
+    /// Circuit inputs proving that leaf `index` is in the tree.
+    pub fn circuit_inputs(
+        &self,
+        index: usize,
+    ) -> Result<NativeGachaCircuitInputs, GachaCircuitError> {
+        let leaf = self.leaf(index)?;
+        Ok(NativeGachaCircuitInputs {
+            merkle_root: self.root(),
+            item_id: leaf.item_id,
+            secret_key: leaf.secret_key,
+            native_merkle_path: self.tree.generate_proof(index)?,
+        })
+    }
+
+    /// The item file (`item_{index}.json`) for leaf `index`.
+    pub fn item_file(&self, index: usize) -> Result<WasmMerkleLeafInputs, GachaCircuitError> {
+        let leaf = self.leaf(index)?;
+        let path = self.tree.generate_proof(index)?;
+        Ok(WasmMerkleLeafInputs {
+            item_id_hex: fr_to_hex(&leaf.item_id)?,
+            secret_key_hex: fr_to_hex(&leaf.secret_key)?,
+            merkle_path_nodes_hex: path
+                .auth_path
+                .iter()
+                .map(fr_to_hex)
+                .collect::<Result<Vec<_>, _>>()?,
+            leaf_sibling_hash_hex: fr_to_hex(&path.leaf_sibling_hash)?,
+            leaf_index: path.leaf_index,
+            field_encoding: FieldEncoding::HexLe,
+        })
+    }
+
+    fn leaf(&self, index: usize) -> Result<&GachaLeaf, GachaCircuitError> {
+        self.leaves.get(index).ok_or_else(|| {
+            GachaCircuitError::InvalidInput(format!(
+                "Leaf index {} is out of range for a tree of {} leaves",
+                index,
+                self.leaves.len()
+            ))
+        })
+    }
+}
@@ -1,13 +1,25 @@
 use crate::error::GachaCircuitError;
+#[cfg(feature = "prover")]
+use crate::hash::{MerkleHash, PoseidonHash};
+#[cfg(feature = "prover")]
+use crate::quaternary::QuaternaryMerklePath;
 use ark_crypto_primitives::sponge::poseidon::PoseidonConfig;
+#[cfg(feature = "prover")]
 use ark_crypto_primitives::{
-    crh::poseidon,
+    crh::{CRHScheme, TwoToOneCRHScheme},
     merkle_tree::{Config as MerkleConfig, IdentityDigestConverter, Path as MerklePath},
 };
 use ark_ff::BigInteger;
 use ark_ff::PrimeField;
+#[cfg(feature = "prover")]
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
+#[cfg(feature = "prover")]
+use ark_std::marker::PhantomData;
 use ark_std::vec::Vec;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "wasm")]
+use tsify::Tsify;
 
 // --- Field Definitions ---
 // This is the field over which the R1CS constraints are defined.
@@ -16,11 +28,12 @@ use serde::{Deserialize, Serialize};
 pub type ConstraintField = ark_bls12_381::Fr;
 
 // --- Native Merkle Tree Configuration ---
-// This configures the native Rust Merkle Tree operations.
-#[derive(Debug, Clone)]
-pub struct GachaMerkleConfig;
+// This configures the native Rust Merkle Tree operations for a given hash backend.
+#[cfg(feature = "prover")]
+pub struct HashMerkleConfig<H: MerkleHash>(PhantomData<H>);
 
-impl MerkleConfig for GachaMerkleConfig {
+#[cfg(feature = "prover")]
+impl<H: MerkleHash> MerkleConfig for HashMerkleConfig<H> {
     // Native leaf data: A slice of ConstraintField elements (e.g., [secret_key, item_id])
     type Leaf = [ConstraintField];
     // Native digest types are ConstraintField elements
@@ -29,13 +42,20 @@ impl MerkleConfig for GachaMerkleConfig {
     // Converter is direct identity since digests are already ConstraintField elements
     type LeafInnerDigestConverter = IdentityDigestConverter<ConstraintField>;
     // Hash functions operate over ConstraintField
-    type LeafHash = poseidon::CRH<ConstraintField>;
-    type TwoToOneHash = poseidon::TwoToOneCRH<ConstraintField>;
+    type LeafHash = H::LeafHash;
+    type TwoToOneHash = H::TwoToOneHash;
 }
 
+// The deployed configuration: Poseidon for both leaf and inner hashes.
+#[cfg(feature = "prover")]
+pub type GachaMerkleConfig = HashMerkleConfig<PoseidonHash>;
+
 // --- Alias for Native Merkle Path ---
 // This path uses the native digest types (ConstraintField)
+#[cfg(feature = "prover")]
 pub type NativeMerklePath = MerklePath<GachaMerkleConfig>;
+#[cfg(feature = "prover")]
+pub type NativeMerklePathFor<H> = MerklePath<HashMerkleConfig<H>>;
 
 // --- Core Native Types ---
 // Configuration for the native Poseidon hash function
@@ -43,7 +63,9 @@ pub type NativePoseidonConfig = PoseidonConfig<ConstraintField>;
 
 // --- WASM Data Transfer Object ---
 // (No changes needed here, hex strings are field-agnostic at this level)
+// `Tsify` emits the matching TypeScript interface into the wasm-pack `.d.ts`.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify), tsify(from_wasm_abi))]
 pub struct WasmGachaCircuitInputs {
     #[serde(rename = "merkleRoot")]
     pub merkle_root_hex: String,
@@ -57,23 +79,309 @@ pub struct WasmGachaCircuitInputs {
     pub leaf_sibling_hash_hex: String,
     #[serde(rename = "leafIndex")]
     pub leaf_index: usize,
+    /// How the field elements above are written; canonical little-endian hex if omitted
+    #[serde(rename = "fieldEncoding", default)]
+    pub field_encoding: FieldEncoding,
+}
+
+// An item file as written by `prepare_gacha_data`: the circuit inputs without the Merkle root.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "wasm", derive(Tsify), tsify(from_wasm_abi))]
+pub struct WasmMerkleLeafInputs {
+    #[serde(rename = "itemIdHex")]
+    pub item_id_hex: String,
+    #[serde(rename = "secretKeyHex")]
+    pub secret_key_hex: String,
+    #[serde(rename = "merklePathNodesHex")]
+    pub merkle_path_nodes_hex: Vec<String>,
+    #[serde(rename = "leafSiblingHashHex")]
+    pub leaf_sibling_hash_hex: String,
+    #[serde(rename = "leafIndex")]
+    pub leaf_index: usize,
+    /// How the field elements above and the root are written; canonical little-endian hex if omitted
+    #[serde(
+        rename = "fieldEncoding",
+        default,
+        skip_serializing_if = "FieldEncoding::is_default"
+    )]
+    pub field_encoding: FieldEncoding,
+}
+
+impl WasmMerkleLeafInputs {
+    /// Completes the leaf inputs with the Merkle root they should lead to.
+    pub fn with_merkle_root(self, merkle_root_hex: String) -> WasmGachaCircuitInputs {
+        WasmGachaCircuitInputs {
+            merkle_root_hex,
+            item_id_hex: self.item_id_hex,
+            secret_key_hex: self.secret_key_hex,
+            merkle_path_nodes_hex: self.merkle_path_nodes_hex,
+            leaf_sibling_hash_hex: self.leaf_sibling_hash_hex,
+            leaf_index: self.leaf_index,
+            field_encoding: self.field_encoding,
+        }
+    }
 }
 
 // --- Native Rust Input Structure (for circuit construction) ---
 // Holds native ConstraintField elements ready for the circuit
+#[cfg(feature = "prover")]
 #[derive(Clone, Debug)]
-pub struct NativeGachaCircuitInputs {
+pub struct NativeGachaCircuitInputs<H: MerkleHash = PoseidonHash> {
     // Public Input
     pub merkle_root: ConstraintField,
     // Private Witness
     pub item_id: ConstraintField,
     pub secret_key: ConstraintField,
-    pub native_merkle_path: NativeMerklePath,
+    pub native_merkle_path: NativeMerklePathFor<H>,
+}
+
+#[cfg(feature = "prover")]
+impl<H: MerkleHash> NativeGachaCircuitInputs<H> {
+    /// All-zero inputs with a path for a tree of `2^tree_height` leaves.
+    /// Only the shape matters, e.g. for key generation or circuit-size measurements.
+    pub fn placeholder(tree_height: usize) -> Self {
+        NativeGachaCircuitInputs {
+            merkle_root: ConstraintField::zero(),
+            item_id: ConstraintField::zero(),
+            secret_key: ConstraintField::zero(),
+            native_merkle_path: NativeMerklePathFor::<H> {
+                leaf_index: 0,
+                auth_path: vec![ConstraintField::zero(); auth_path_len(tree_height)],
+                leaf_sibling_hash: ConstraintField::zero(),
+            },
+        }
+    }
+
+    /// Checks natively that these inputs satisfy `UserPullCircuit` for a tree of
+    /// `2^tree_height` leaves, so that a corrupted item file fails here with a precise
+    /// [`GachaCircuitError::InvalidInput`] instead of yielding a proof that does not verify.
+    pub fn validate(
+        &self,
+        params: &H::Parameters,
+        tree_height: usize,
+    ) -> Result<(), GachaCircuitError> {
+        self.check_path_shape(tree_height)?;
+        let (leaf_digest, root) = self.path_root(params)?;
+        if root != self.merkle_root {
+            return Err(GachaCircuitError::InvalidInput(format!(
+                "Leaf {} (digest {}) does not belong to Merkle root {}: its path leads to {}. \
+                 The secret key, item id or path is wrong, or is from another tree",
+                self.native_merkle_path.leaf_index,
+                fr_to_hex(&leaf_digest)?,
+                fr_to_hex(&self.merkle_root)?,
+                fr_to_hex(&root)?
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether the path leads from the leaf `[secret_key, item_id]` to `merkle_root`.
+    /// Unlike [`Self::validate`], a path to another root is `Ok(false)`; a path of the wrong
+    /// shape for `tree_height` is still an error.
+    pub fn verify_merkle_path(
+        &self,
+        params: &H::Parameters,
+        tree_height: usize,
+    ) -> Result<bool, GachaCircuitError> {
+        self.check_path_shape(tree_height)?;
+        let (_, root) = self.path_root(params)?;
+        Ok(root == self.merkle_root)
+    }
+
+    /// Checks that the path has the shape the keys for `2^tree_height` leaves were generated for.
+    fn check_path_shape(&self, tree_height: usize) -> Result<(), GachaCircuitError> {
+        let path = &self.native_merkle_path;
+        let expected_len = auth_path_len(tree_height);
+        if path.auth_path.len() != expected_len {
+            return Err(GachaCircuitError::InvalidInput(format!(
+                "Merkle path has {} nodes, but a tree of height {} needs {}",
+                path.auth_path.len(),
+                tree_height,
+                expected_len
+            )));
+        }
+        if path.leaf_index.checked_shr(tree_height as u32).unwrap_or(0) != 0 {
+            return Err(GachaCircuitError::InvalidInput(format!(
+                "Leaf index {} is out of range for a tree of height {}",
+                path.leaf_index, tree_height
+            )));
+        }
+        Ok(())
+    }
+
+    /// Recomputes the leaf digest and walks the path up to the root, as the circuit does.
+    /// Returns `(leaf_digest, root)`.
+    fn path_root(
+        &self,
+        params: &H::Parameters,
+    ) -> Result<(ConstraintField, ConstraintField), GachaCircuitError> {
+        let path = &self.native_merkle_path;
+        let leaf_digest = leaf_digest::<H>(params, self.secret_key, self.item_id)?;
+        let (left, right) = ordered_children(path.leaf_index, leaf_digest, path.leaf_sibling_hash);
+        let mut node = H::TwoToOneHash::evaluate(params, left, right)?;
+        let mut index = path.leaf_index >> 1;
+        for sibling in path.auth_path.iter().rev() {
+            let (left, right) = ordered_children(index, node, *sibling);
+            node = H::TwoToOneHash::compress(params, left, right)?;
+            index >>= 1;
+        }
+        Ok((leaf_digest, node))
+    }
+}
+
+/// Digest of the Merkle leaf `[secret_key, item_id]`.
+#[cfg(feature = "prover")]
+pub fn leaf_digest<H: MerkleHash>(
+    params: &H::Parameters,
+    secret_key: ConstraintField,
+    item_id: ConstraintField,
+) -> Result<ConstraintField, GachaCircuitError> {
+    Ok(H::LeafHash::evaluate(
+        params,
+        [secret_key, item_id].as_slice(),
+    )?)
+}
+
+/// Orders `node` and its sibling as (left, right) by the parity of `index`.
+#[cfg(feature = "prover")]
+fn ordered_children(
+    index: usize,
+    node: ConstraintField,
+    sibling: ConstraintField,
+) -> (ConstraintField, ConstraintField) {
+    if index & 1 == 0 {
+        (node, sibling)
+    } else {
+        (sibling, node)
+    }
+}
+
+// Native inputs for `QuaternaryUserPullCircuit`: same leaf and root, 4-ary path
+#[cfg(feature = "prover")]
+#[derive(Clone, Debug)]
+pub struct QuaternaryGachaCircuitInputs {
+    // Public Input
+    pub merkle_root: ConstraintField,
+    // Private Witness
+    pub item_id: ConstraintField,
+    pub secret_key: ConstraintField,
+    pub merkle_path: QuaternaryMerklePath,
+}
+
+#[cfg(feature = "prover")]
+impl QuaternaryGachaCircuitInputs {
+    /// All-zero inputs with a path for a quaternary tree of `4^depth` leaves.
+    pub fn placeholder(depth: usize) -> Self {
+        QuaternaryGachaCircuitInputs {
+            merkle_root: ConstraintField::zero(),
+            item_id: ConstraintField::zero(),
+            secret_key: ConstraintField::zero(),
+            merkle_path: QuaternaryMerklePath::placeholder(depth),
+        }
+    }
+}
+
+/// Largest tree height `GachaTree::build` and the key file decoders accept (16M leaves).
+pub const MAX_TREE_HEIGHT: usize = 24;
+
+/// Length of `NativeMerklePath::auth_path` for a tree with `2^tree_height` leaves.
+/// arkworks stores the leaf's sibling separately in `leaf_sibling_hash`.
+pub fn auth_path_len(tree_height: usize) -> usize {
+    tree_height.saturating_sub(1)
 }
 
 // --- Conversion Functions ---
 
+/// String encodings of a field element, as named in JSON (`"hex-le"`, `"hex-be"`, ...).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "wasm", derive(Tsify))]
+#[serde(rename_all = "kebab-case")]
+pub enum FieldEncoding {
+    /// 32 bytes little-endian as 64 hex digits, `0x` optional; what [`fr_to_hex`] writes
+    #[default]
+    HexLe,
+    /// 32 bytes big-endian as 64 hex digits, `0x` optional; what most other tooling writes
+    HexBe,
+    /// Base-10 digits without sign or separators
+    Decimal,
+    /// Any-length little-endian hex reduced modulo the field order, as [`fr_from_hex`] reads.
+    /// Only for data written by older tools; decoding cannot tell corrupted values apart.
+    HexLeModOrder,
+}
+
+impl FieldEncoding {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Byte length of a canonically encoded field element.
+const FIELD_ELEMENT_BYTES: usize = 32;
+
+/// Decodes a field element, rejecting values that are not canonical for `encoding`:
+/// hex of the wrong length, values not below the field modulus, and stray characters.
+pub fn fr_decode(
+    value: &str,
+    encoding: FieldEncoding,
+) -> Result<ConstraintField, GachaCircuitError> {
+    let mut bytes = match encoding {
+        FieldEncoding::HexLeModOrder => return fr_from_hex(value),
+        FieldEncoding::Decimal => return fr_from_decimal(value),
+        FieldEncoding::HexLe | FieldEncoding::HexBe => {
+            let digits = value.strip_prefix("0x").unwrap_or(value);
+            if digits.len() != 2 * FIELD_ELEMENT_BYTES {
+                return Err(GachaCircuitError::InvalidInput(format!(
+                    "Field element '{}' must be {} hex digits, got {}",
+                    value,
+                    2 * FIELD_ELEMENT_BYTES,
+                    digits.len()
+                )));
+            }
+            hex::decode(digits)?
+        }
+    };
+    if encoding == FieldEncoding::HexBe {
+        bytes.reverse();
+    }
+    let bigint = <ConstraintField as PrimeField>::BigInt::deserialize_uncompressed(&bytes[..])?;
+    ConstraintField::from_bigint(bigint).ok_or_else(|| not_below_modulus(value))
+}
+
+/// Encodes a field element canonically; [`FieldEncoding::HexLeModOrder`] writes `HexLe`.
+pub fn fr_encode(fr: &ConstraintField, encoding: FieldEncoding) -> String {
+    let bigint = fr.into_bigint();
+    match encoding {
+        FieldEncoding::HexLe | FieldEncoding::HexLeModOrder => {
+            format!("0x{}", hex::encode(bigint.to_bytes_le()))
+        }
+        FieldEncoding::HexBe => format!("0x{}", hex::encode(bigint.to_bytes_be())),
+        FieldEncoding::Decimal => bigint.to_string(),
+    }
+}
+
+fn fr_from_decimal(value: &str) -> Result<ConstraintField, GachaCircuitError> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(GachaCircuitError::InvalidInput(format!(
+            "Field element '{}' is not a decimal number",
+            value
+        )));
+    }
+    // Too large for the limbs is also not below the modulus
+    let bigint: <ConstraintField as PrimeField>::BigInt =
+        value.parse().map_err(|_| not_below_modulus(value))?;
+    ConstraintField::from_bigint(bigint).ok_or_else(|| not_below_modulus(value))
+}
+
+fn not_below_modulus(value: &str) -> GachaCircuitError {
+    GachaCircuitError::InvalidInput(format!(
+        "Field element '{}' is not below the field modulus",
+        value
+    ))
+}
+
 /// Converts a hex string to ConstraintField.
+/// Lenient: any length is accepted and reduced modulo the field order; see [`fr_decode`] for
+/// the strict decoder.
 pub fn fr_from_hex(hex_str: &str) -> Result<ConstraintField, GachaCircuitError> {
     let stripped = hex_str.trim_start_matches("0x");
     let bytes = hex::decode(stripped)?;
@@ -87,19 +395,26 @@ pub fn fr_to_hex(fr: &ConstraintField) -> Result<String, GachaCircuitError> {
 }
 
 /// Converts WASM DTO to Native Rust structure.
+#[cfg(feature = "prover")]
 impl TryFrom<WasmGachaCircuitInputs> for NativeGachaCircuitInputs {
     type Error = GachaCircuitError;
 
     fn try_from(wasm_inputs: WasmGachaCircuitInputs) -> Result<Self, Self::Error> {
-        let merkle_root = fr_from_hex(&wasm_inputs.merkle_root_hex)?;
-        let item_id = fr_from_hex(&wasm_inputs.item_id_hex)?;
-        let secret_key = fr_from_hex(&wasm_inputs.secret_key_hex)?;
-        let leaf_sibling_hash = fr_from_hex(&wasm_inputs.leaf_sibling_hash_hex)?;
+        // Errors name the input by its JS name
+        let decode = |field: &str, value: &str| {
+            fr_decode(value, wasm_inputs.field_encoding)
+                .map_err(|e| GachaCircuitError::invalid_field(field, e))
+        };
+        let merkle_root = decode("merkleRoot", &wasm_inputs.merkle_root_hex)?;
+        let item_id = decode("itemIdHex", &wasm_inputs.item_id_hex)?;
+        let secret_key = decode("secretKeyHex", &wasm_inputs.secret_key_hex)?;
+        let leaf_sibling_hash = decode("leafSiblingHashHex", &wasm_inputs.leaf_sibling_hash_hex)?;
 
         let auth_path_nodes = wasm_inputs
             .merkle_path_nodes_hex
             .iter()
-            .map(|args| fr_from_hex(args))
+            .enumerate()
+            .map(|(i, node)| decode(&format!("merklePathNodesHex[{}]", i), node))
             .collect::<Result<Vec<_>, _>>()?;
 
         let native_merkle_path = NativeMerklePath {
@@ -4,6 +4,9 @@ use ark_std::{
 };
 use getrandom::getrandom;
 
+pub mod serialization;
+pub(crate) mod transcript;
+
 pub fn get_rng(seed: Option<u64>) -> Result<StdRng, String> {
     match seed {
         Some(seed) => Ok(StdRng::seed_from_u64(seed)),
@@ -0,0 +1,51 @@
+use ark_serialize::{CanonicalDeserialize, Compress, SerializationError, Validate};
+use ark_std::{format, string::ToString};
+
+use crate::error::GachaCircuitError;
+
+/// How much validation to apply to curve points when deserializing keys and proofs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeserializationPolicy {
+    /// Every point must be on the curve and in the prime-order subgroup.
+    /// Use for anything that came over the network.
+    #[default]
+    Checked,
+    /// Skip point validation. Only for trusted local files; faster for large proving keys.
+    Unchecked,
+}
+
+impl DeserializationPolicy {
+    fn validate(self) -> Validate {
+        match self {
+            DeserializationPolicy::Checked => Validate::Yes,
+            DeserializationPolicy::Unchecked => Validate::No,
+        }
+    }
+}
+
+/// What a deserialized value is made of, which decides how malformed data is reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueKind {
+    /// Proofs, keys and SRS: malformed data is an invalid curve point.
+    CurvePoints,
+    /// Field elements and plain data such as Poseidon parameters.
+    FieldElements,
+}
+
+/// Deserializes a compressed `T` under `policy`.
+/// For [`ValueKind::CurvePoints`], points that fail validation are reported as
+/// [`GachaCircuitError::InvalidPoint`] naming `what`; any other failure is a
+/// [`GachaCircuitError::Deserialization`].
+pub fn deserialize_compressed<T: CanonicalDeserialize>(
+    what: &str,
+    bytes: &[u8],
+    kind: ValueKind,
+    policy: DeserializationPolicy,
+) -> Result<T, GachaCircuitError> {
+    T::deserialize_with_mode(bytes, Compress::Yes, policy.validate()).map_err(|e| match (e, kind) {
+        (SerializationError::InvalidData, ValueKind::CurvePoints) => {
+            GachaCircuitError::InvalidPoint(what.to_string())
+        }
+        (e, _) => GachaCircuitError::Deserialization(format!("{}: {}", what, e)),
+    })
+}
@@ -0,0 +1,56 @@
+use ark_bls12_381::Fr;
+use ark_ff::{PrimeField, Zero};
+use ark_serialize::CanonicalSerialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::GachaCircuitError;
+
+/// Fiat-Shamir transcript: a running SHA-256 chain over labelled, compressed elements.
+pub(crate) struct Transcript {
+    state: [u8; 32],
+}
+
+impl Transcript {
+    pub fn new(domain: &[u8]) -> Self {
+        Self {
+            state: Sha256::digest(domain).into(),
+        }
+    }
+
+    /// Absorbs a labelled element into the transcript.
+    pub fn append<T: CanonicalSerialize + ?Sized>(
+        &mut self,
+        label: &[u8],
+        value: &T,
+    ) -> Result<(), GachaCircuitError> {
+        let mut bytes = Vec::new();
+        value.serialize_compressed(&mut bytes)?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.state);
+        hasher.update(label);
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(&bytes);
+        self.state = hasher.finalize().into();
+        Ok(())
+    }
+
+    /// Squeezes a non-zero challenge, so it can always be inverted.
+    pub fn challenge(&mut self, label: &[u8]) -> Fr {
+        let mut counter = 0u64;
+        loop {
+            let mut hasher = Sha256::new();
+            hasher.update(self.state);
+            hasher.update(label);
+            hasher.update(counter.to_le_bytes());
+            let digest: [u8; 32] = hasher.finalize().into();
+            self.state = digest;
+
+            let challenge = Fr::from_le_bytes_mod_order(&digest);
+            if !challenge.is_zero() {
+                return challenge;
+            }
+            counter += 1;
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Proving from a Web Worker.
+//!
+//! The main thread fetches the key files and transfers their `ArrayBuffer`s (or shares them as
+//! `SharedArrayBuffer`s) to a worker, which builds a [`WorkerProver`] from them. Proof requests
+//! carry the circuit inputs as a JSON string and come back as a fresh `ArrayBuffer` that can be
+//! transferred to the main thread without copying. Nothing here touches the pool registry, so
+//! every worker owns its keys. `apps/web/lib/proverWorker.ts` is the matching worker script.
+
+use ark_std::{format, vec::Vec};
+use js_sys::{ArrayBuffer, Function, Uint8Array};
+use wasm_bindgen::{prelude::*, JsCast};
+
+use crate::api::{prove_wasm_inputs, JsProgress, ProofEncoding};
+use crate::error::GachaCircuitError;
+use crate::keys::GachaKeys;
+use crate::native::GachaProver;
+use crate::types::WasmGachaCircuitInputs;
+
+/// A prover owning its keys, built from transferred or shared buffers.
+#[wasm_bindgen]
+pub struct WorkerProver {
+    prover: GachaProver,
+}
+
+#[wasm_bindgen]
+impl WorkerProver {
+    /// Loads the key files from `ArrayBuffer`s, `SharedArrayBuffer`s or `Uint8Array`s,
+    /// with the same checks as `init_gacha_keys`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        #[wasm_bindgen(unchecked_param_type = "ArrayBuffer | SharedArrayBuffer | Uint8Array")]
+        pk_buffer: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "ArrayBuffer | SharedArrayBuffer | Uint8Array")]
+        vk_buffer: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "ArrayBuffer | SharedArrayBuffer | Uint8Array")]
+        params_buffer: JsValue,
+    ) -> Result<WorkerProver, JsValue> {
+        let pk_bytes = buffer_bytes("Proving key", &pk_buffer)?;
+        let keys = GachaKeys::decode(
+            &pk_bytes,
+            &buffer_bytes("Verifying key", &vk_buffer)?,
+            &buffer_bytes("Poseidon parameters", &params_buffer)?,
+            crate::bindings::deserialization_policy(),
+        )?;
+        Ok(Self {
+            prover: GachaProver::new(keys)?,
+        })
+    }
+
+    /// Proves from `JSON.stringify(inputs)` and returns the compressed proof as a transferable
+    /// `ArrayBuffer`. `on_progress` and `cancel_token` are optional and work as in
+    /// `generate_gacha_proof_with_progress`.
+    #[wasm_bindgen(js_name = proveSerialized)]
+    pub fn prove_serialized(
+        &self,
+        inputs_json: &str,
+        #[wasm_bindgen(unchecked_param_type = "ProveProgressCallback | undefined")]
+        on_progress: Option<Function>,
+        #[wasm_bindgen(unchecked_param_type = "CancellationToken | undefined")]
+        cancel_token: JsValue,
+    ) -> Result<ArrayBuffer, JsValue> {
+        let wasm_inputs: WasmGachaCircuitInputs = serde_json::from_str(inputs_json)
+            .map_err(|e| GachaCircuitError::Deserialization(format!("WASM inputs: {}", e)))?;
+        let progress = JsProgress {
+            on_progress,
+            cancel_token,
+        };
+        let proof_bytes = prove_wasm_inputs(
+            &self.prover,
+            wasm_inputs,
+            Some(&progress),
+            ProofEncoding::Raw,
+        )?;
+        Ok(Uint8Array::from(proof_bytes.as_slice()).buffer())
+    }
+
+    /// The Merkle tree has `2^treeHeight` leaves.
+    #[wasm_bindgen(getter, js_name = treeHeight)]
+    pub fn tree_height(&self) -> usize {
+        self.prover.tree_height()
+    }
+}
+
+/// Copies the contents of a `Uint8Array`, or of an `ArrayBuffer` / `SharedArrayBuffer`.
+fn buffer_bytes(name: &str, buffer: &JsValue) -> Result<Vec<u8>, GachaCircuitError> {
+    match buffer.dyn_ref::<Uint8Array>() {
+        Some(view) => Ok(view.to_vec()),
+        None if buffer.is_object() => Ok(Uint8Array::new(buffer).to_vec()),
+        None => Err(GachaCircuitError::InvalidInput(format!(
+            "{} must be an ArrayBuffer, SharedArrayBuffer or Uint8Array",
+            name
+        ))),
+    }
+}
@@ -1,60 +1,64 @@
 //! Integration tests for the zk_circuits package.
 //! These tests run natively using `cargo test`.
 
-use ark_bls12_381::{Bls12_381, Fr}; // Use Fr directly as ConstraintField
+use ark_bls12_381::{Bls12_381, Fq, Fr, G1Affine}; // Use Fr directly as ConstraintField
 use ark_crypto_primitives::crh::CRHScheme;
 use ark_crypto_primitives::{
     crh::poseidon::CRH as PoseidonCRH,
     merkle_tree::MerkleTree, // Native Merkle Tree
-    sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig},
+    sponge::{poseidon::PoseidonSponge, CryptographicSponge, FieldBasedCryptographicSponge},
 };
-use ark_ff::{PrimeField, UniformRand};
-use ark_groth16::{Groth16, PreparedVerifyingKey, ProvingKey, VerifyingKey};
+use ark_ec::AffineRepr;
+use ark_ff::{BigInteger, PrimeField, UniformRand};
+use ark_groth16::{Groth16, PreparedVerifyingKey, Proof, ProvingKey, VerifyingKey};
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use ark_serialize::CanonicalSerialize;
 use ark_snark::SNARK;
 use ark_std::rand::RngCore;
 use ark_std::rand::SeedableRng;
 use ark_std::{test_rng, vec::Vec};
 
 // Import types and circuit from the library crate
+#[cfg(feature = "poseidon2")]
+use zk_circuits::hash::Poseidon2Hash;
+#[cfg(feature = "rescue")]
+use zk_circuits::hash::RescuePrimeHash;
+#[cfg(any(feature = "std", feature = "wasm"))]
+use zk_circuits::keys::KeyFileKind;
 use zk_circuits::{
+    aggregation::{aggregate_proofs, verify_aggregate_proof, AggregationSrs},
+    batch::verify_gacha_proofs_batch,
+    circuit::{QuaternaryUserPullCircuit, UserPullCircuit},
+    envelope::{GachaProofEnvelope, ENVELOPE_MAGIC, MAX_ID_LEN},
     error::GachaCircuitError,
-    circuit::UserPullCircuit, // Optional: Use if testing error conditions
+    hash::{MerkleHash, PoseidonHash},
+    keys::{poseidon_params_hash, GachaKeys, VerifierKeys, KEY_FILE_MAGIC},
+    native,
+    pool::{GachaPool, PoolRegistry},
+    poseidon::{default_poseidon_config, poseidon_config, round_numbers, RoundNumbers},
+    profiling::profile_circuits,
+    prover::{num_threads, prove_pull, prove_pull_with_progress, prove_pulls, ProvePhase},
+    quaternary::{quaternary_depth, QuaternaryMerkleParams, QuaternaryMerkleTree},
+    tree::{GachaLeaf, GachaTree},
     types::{
-        prepare_groth16_public_inputs, ConstraintField, GachaMerkleConfig,
-        NativeGachaCircuitInputs, NativePoseidonConfig,
+        fr_decode, fr_encode, fr_from_hex, fr_to_hex, leaf_digest, prepare_groth16_public_inputs,
+        ConstraintField, FieldEncoding, GachaMerkleConfig, HashMerkleConfig,
+        NativeGachaCircuitInputs, NativePoseidonConfig, QuaternaryGachaCircuitInputs,
+        WasmGachaCircuitInputs, WasmMerkleLeafInputs, MAX_TREE_HEIGHT,
     },
+    utils::serialization::{deserialize_compressed, DeserializationPolicy, ValueKind},
+};
+#[cfg(feature = "wasm")]
+use zk_circuits::{
+    api::{GachaTreeBuilder, JsGachaProver, JsGachaVerifier},
+    keys::encode_key_file,
 };
 
 // --- Helper Functions for Tests ---
 
-/// Generates default Poseidon parameters for testing.
+/// The Poseidon parameters the circuit is deployed with.
 fn setup_poseidon_params() -> NativePoseidonConfig {
-    // Use standard configuration for Poseidon with rate=2
-    let rate = 2;
-    let capacity = 1;
-    let full_rounds = 8;
-    // Using 31 based on reference benchmarks, adjust if needed for security level
-    let partial_rounds = 31;
-    let alpha = 17; // Common choice for BLS12-381 Fr
-
-    let (ark, mds) = find_poseidon_ark_and_mds::<ConstraintField>(
-        ConstraintField::MODULUS_BIT_SIZE as u64,
-        rate + capacity,
-        full_rounds as u64,
-        partial_rounds as u64,
-        0, // skip matrices
-    );
-
-    PoseidonConfig {
-        full_rounds,
-        partial_rounds,
-        alpha: alpha as u64,
-        ark,
-        mds,
-        rate,
-        capacity,
-    }
+    default_poseidon_config()
 }
 
 /// Creates valid test data including inputs and the Merkle tree components.
@@ -116,20 +120,20 @@ fn create_test_data(
     Ok((circuit_inputs, merkle_root))
 }
 
+/// Proving key, verifying key and prepared verifying key produced by a test setup.
+type Groth16Keys = (
+    ProvingKey<Bls12_381>,
+    VerifyingKey<Bls12_381>,
+    PreparedVerifyingKey<Bls12_381>,
+);
+
 /// Sets up Groth16 keys for a given circuit instance.
 /// This is slower than loading pre-generated keys but useful for testing.
 fn setup_groth16_keys(
     poseidon_params: &NativePoseidonConfig,
     tree_size: usize, // Must be power of 2
     target_leaf_index: usize,
-) -> Result<
-    (
-        ProvingKey<Bls12_381>,
-        VerifyingKey<Bls12_381>,
-        PreparedVerifyingKey<Bls12_381>,
-    ),
-    Box<dyn std::error::Error>,
-> {
+) -> Result<Groth16Keys, Box<dyn std::error::Error>> {
     let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(ark_std::test_rng().next_u64());
     // Create a dummy circuit instance just for setup
     let (dummy_inputs, _) = create_test_data(poseidon_params, tree_size, target_leaf_index)?; // Small tree for setup
@@ -303,3 +307,1296 @@ fn test_invalid_proof_wrong_public_input() {
         "Proof verification succeeded with incorrect public input"
     );
 }
+
+#[test]
+fn test_batch_verification_reports_invalid_indices() {
+    let params = setup_poseidon_params();
+    let tree_size = 16;
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(ark_std::test_rng().next_u64());
+
+    // 1. Setup keys
+    let (pk, _vk, pvk) = setup_groth16_keys(&params, tree_size, 0).expect("Key setup failed");
+
+    // 2. Generate a few valid proofs for different leaves
+    let mut roots = Vec::new();
+    let mut proofs = Vec::new();
+    for target_leaf_index in [1, 6, 11] {
+        let (inputs, merkle_root) = create_test_data(&params, tree_size, target_leaf_index)
+            .expect("Failed to create test data");
+        let circuit = UserPullCircuit::new(inputs, params.clone());
+        proofs.push(Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).expect("Proving failed"));
+        roots.push(merkle_root);
+    }
+
+    // 3. The whole batch verifies
+    let result = verify_gacha_proofs_batch(&pvk, &roots, &proofs, &mut rng)
+        .expect("Batch verification returned error");
+    assert!(result.valid, "Valid batch was rejected");
+    assert!(result.invalid_indices.is_empty());
+
+    // 4. A wrong root at index 1 is pinpointed
+    roots[1] = Fr::rand(&mut rng);
+    let result = verify_gacha_proofs_batch(&pvk, &roots, &proofs, &mut rng)
+        .expect("Batch verification returned error");
+    assert!(!result.valid, "Batch with a bad proof was accepted");
+    assert_eq!(result.invalid_indices, vec![1]);
+
+    // 5. Mismatched lengths are rejected as invalid input
+    let mismatch = verify_gacha_proofs_batch(&pvk, &roots[..2], &proofs, &mut rng);
+    assert!(matches!(mismatch, Err(GachaCircuitError::InvalidInput(_))));
+}
+
+#[test]
+fn test_proof_aggregation_roundtrip() {
+    let params = setup_poseidon_params();
+    let tree_size = 8;
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(ark_std::test_rng().next_u64());
+
+    // 1. Setup keys and an aggregation SRS
+    let (pk, _vk, pvk) = setup_groth16_keys(&params, tree_size, 0).expect("Key setup failed");
+    let srs = AggregationSrs::setup(&mut rng, 4);
+    let verifier_srs = srs.verifier_srs().expect("Verifier SRS extraction failed");
+
+    // 2. Generate three proofs; the aggregator pads them to four
+    let mut roots = Vec::new();
+    let mut proofs = Vec::new();
+    for target_leaf_index in [2, 5, 7] {
+        let (inputs, merkle_root) = create_test_data(&params, tree_size, target_leaf_index)
+            .expect("Failed to create test data");
+        let circuit = UserPullCircuit::new(inputs, params.clone());
+        proofs.push(Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).expect("Proving failed"));
+        roots.push(merkle_root);
+    }
+
+    // 3. Aggregate and verify
+    let prover_srs = srs.prover_srs(4).expect("Prover SRS specialization failed");
+    let aggregate = aggregate_proofs(&prover_srs, &roots, &proofs).expect("Aggregation failed");
+    assert_eq!(aggregate.rounds.len(), 2);
+    assert!(
+        verify_aggregate_proof(&verifier_srs, &pvk, &roots, &aggregate)
+            .expect("Aggregate verification returned error"),
+        "Aggregate proof verification failed"
+    );
+
+    // 4. The aggregate does not verify against a different root
+    let mut wrong_roots = roots.clone();
+    wrong_roots[0] = Fr::rand(&mut rng);
+    assert!(
+        !verify_aggregate_proof(&verifier_srs, &pvk, &wrong_roots, &aggregate)
+            .expect("Aggregate verification returned error"),
+        "Aggregate proof verified against a wrong root"
+    );
+
+    // 5. Aggregating a proof for the wrong root yields an aggregate that fails verification
+    let mut tampered = proofs.clone();
+    tampered.swap(0, 1);
+    let bad_aggregate =
+        aggregate_proofs(&prover_srs, &roots, &tampered).expect("Aggregation failed");
+    assert!(
+        !verify_aggregate_proof(&verifier_srs, &pvk, &roots, &bad_aggregate)
+            .expect("Aggregate verification returned error"),
+        "Aggregate of mismatched proofs verified"
+    );
+}
+
+#[test]
+fn test_key_files_roundtrip_and_validation() {
+    let params = setup_poseidon_params();
+    let tree_size: usize = 8;
+    let tree_height = tree_size.trailing_zeros() as usize;
+    let (pk, vk, _pvk) = setup_groth16_keys(&params, tree_size, 0).expect("Key setup failed");
+
+    // 1. Encoded files decode back to the same keys
+    let keys = GachaKeys {
+        proving_key: pk.clone(),
+        verifying_key: vk.clone(),
+        poseidon_params: params.clone(),
+        tree_height,
+    };
+    let encoded = keys.encode().expect("Encoding keys failed");
+    let decoded = GachaKeys::decode(
+        &encoded.proving_key,
+        &encoded.verifying_key,
+        &encoded.poseidon_params,
+        DeserializationPolicy::Checked,
+    )
+    .expect("Decoding valid key files failed");
+    assert_eq!(decoded.verifying_key, vk);
+    assert_eq!(decoded.tree_height, tree_height);
+
+    let is_invalid_key_file = |result: Result<GachaKeys, GachaCircuitError>| {
+        matches!(result, Err(GachaCircuitError::InvalidKeyFile(_)))
+    };
+
+    // 2. Raw arkworks blobs from before the container format are rejected
+    let mut raw_vk = Vec::new();
+    vk.serialize_compressed(&mut raw_vk).unwrap();
+    assert!(is_invalid_key_file(GachaKeys::decode(
+        &encoded.proving_key,
+        &raw_vk,
+        &encoded.poseidon_params,
+        DeserializationPolicy::Checked
+    )));
+
+    // 3. Files passed in the wrong slot are rejected
+    assert!(is_invalid_key_file(GachaKeys::decode(
+        &encoded.proving_key,
+        &encoded.poseidon_params,
+        &encoded.verifying_key,
+        DeserializationPolicy::Checked
+    )));
+
+    // 4. A corrupted payload is rejected
+    let mut corrupted_pk = encoded.proving_key.clone();
+    *corrupted_pk.last_mut().unwrap() ^= 1;
+    assert!(is_invalid_key_file(GachaKeys::decode(
+        &corrupted_pk,
+        &encoded.verifying_key,
+        &encoded.poseidon_params,
+        DeserializationPolicy::Checked
+    )));
+
+    // 5. Keys recorded for the wrong tree height are rejected, and cannot be mixed with others
+    let wrong_height = GachaKeys {
+        tree_height: tree_height + 1,
+        ..keys.clone()
+    }
+    .encode()
+    .expect("Encoding keys failed");
+    assert!(is_invalid_key_file(GachaKeys::decode(
+        &wrong_height.proving_key,
+        &wrong_height.verifying_key,
+        &wrong_height.poseidon_params,
+        DeserializationPolicy::Checked
+    )));
+    assert!(is_invalid_key_file(GachaKeys::decode(
+        &encoded.proving_key,
+        &encoded.verifying_key,
+        &wrong_height.poseidon_params,
+        DeserializationPolicy::Checked
+    )));
+
+    // 6. The verifier-only loader needs just the verifying key and parameters
+    let verifier_keys = VerifierKeys::decode(
+        &encoded.verifying_key,
+        &encoded.poseidon_params,
+        DeserializationPolicy::Checked,
+    )
+    .expect("Decoding verifier key files failed");
+    assert_eq!(verifier_keys.verifying_key, vk);
+    assert_eq!(verifier_keys.tree_height, tree_height);
+    assert!(matches!(
+        VerifierKeys::decode(
+            &encoded.verifying_key,
+            &wrong_height.poseidon_params,
+            DeserializationPolicy::Checked
+        ),
+        Err(GachaCircuitError::InvalidKeyFile(_))
+    ));
+
+    // 7. Out-of-range tree heights are rejected before any circuit is synthesized for them
+    for bad_height in [0, MAX_TREE_HEIGHT + 1, u32::MAX as usize] {
+        let bad = GachaKeys {
+            tree_height: bad_height,
+            ..keys.clone()
+        }
+        .encode()
+        .expect("Encoding keys failed");
+        assert!(is_invalid_key_file(GachaKeys::decode(
+            &bad.proving_key,
+            &bad.verifying_key,
+            &bad.poseidon_params,
+            DeserializationPolicy::Checked
+        )));
+        assert!(matches!(
+            VerifierKeys::decode(
+                &bad.verifying_key,
+                &bad.poseidon_params,
+                DeserializationPolicy::Checked
+            ),
+            Err(GachaCircuitError::InvalidKeyFile(_))
+        ));
+    }
+
+    // 8. A truncated header is a bad key file, not a serialization error
+    assert!(is_invalid_key_file(GachaKeys::decode(
+        &encoded.proving_key,
+        &encoded.verifying_key,
+        &encoded.poseidon_params[..KEY_FILE_MAGIC.len() + 4],
+        DeserializationPolicy::Checked
+    )));
+}
+
+#[test]
+fn test_checked_deserialization_rejects_invalid_points() {
+    let params = setup_poseidon_params();
+    let tree_size = 8;
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(ark_std::test_rng().next_u64());
+    let (pk, _vk, _pvk) = setup_groth16_keys(&params, tree_size, 0).expect("Key setup failed");
+    let (inputs, _) = create_test_data(&params, tree_size, 0).expect("Failed to create test data");
+    let proof = Groth16::<Bls12_381>::prove(&pk, UserPullCircuit::new(inputs, params), &mut rng)
+        .expect("Proving failed");
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+
+    // 1. A valid proof passes the checked path
+    let decoded: Proof<Bls12_381> = deserialize_compressed(
+        "Proof",
+        &proof_bytes,
+        ValueKind::CurvePoints,
+        DeserializationPolicy::Checked,
+    )
+    .expect("Checked deserialization of a valid proof failed");
+    assert_eq!(decoded, proof);
+
+    // 2. Replace `A` with a curve point outside the prime-order subgroup
+    let mut x = Fq::from(1u64);
+    let low_order_point = loop {
+        if let Some(point) = G1Affine::get_point_from_x_unchecked(x, false) {
+            if !point.is_in_correct_subgroup_assuming_on_curve() {
+                break point;
+            }
+        }
+        x += Fq::from(1u64);
+    };
+    let mut tampered = proof_bytes.clone();
+    low_order_point
+        .serialize_compressed(&mut tampered[..G1Affine::zero().compressed_size()])
+        .unwrap();
+
+    let checked: Result<Proof<Bls12_381>, _> = deserialize_compressed(
+        "Proof",
+        &tampered,
+        ValueKind::CurvePoints,
+        DeserializationPolicy::Checked,
+    );
+    assert!(matches!(checked, Err(GachaCircuitError::InvalidPoint(_))));
+    let unchecked: Result<Proof<Bls12_381>, _> = deserialize_compressed(
+        "Proof",
+        &tampered,
+        ValueKind::CurvePoints,
+        DeserializationPolicy::Unchecked,
+    );
+    assert!(
+        unchecked.is_ok(),
+        "Unchecked policy should skip subgroup checks"
+    );
+}
+
+#[test]
+fn test_poseidon_parameter_known_answers() {
+    // 1. Round numbers for (security level, width, alpha), margin included. Expected values are
+    // arkworks' BLS12-381 Fr table (`PoseidonDefaultConfig` in ark-crypto-primitives 0.5.0,
+    // src/sponge/test.rs, rate `t - 1`). Its rate-4 entry (t = 5, R_P = 57) is left out: the
+    // interpolation bound of calc_round_numbers.py uses ceil(log_alpha(t)), which is exactly 1 at
+    // t = alpha = 5, and the derivation gives 56 there.
+    let vectors = [
+        ((128, 3, 17), (8, 31)),
+        ((128, 4, 5), (8, 56)),
+        ((128, 6, 5), (8, 57)),
+        ((128, 7, 5), (8, 57)),
+        ((128, 8, 5), (8, 57)),
+        ((128, 9, 5), (8, 57)),
+    ];
+    for ((security_level, width, alpha), (full_rounds, partial_rounds)) in vectors {
+        assert_eq!(
+            round_numbers(security_level, width, alpha).expect("Round derivation failed"),
+            RoundNumbers {
+                full_rounds,
+                partial_rounds
+            },
+            "Round numbers for M={}, t={}, alpha={}",
+            security_level,
+            width,
+            alpha
+        );
+    }
+
+    // 2. Exponents that are not permutations of Fr and out-of-range levels are rejected
+    assert!(round_numbers(128, 3, 3).is_err());
+    assert!(round_numbers(128, 3, 11).is_err());
+    assert!(round_numbers(256, 3, 17).is_err());
+
+    // 3. The default parameters are the width-3 instance: one constant per cell and round
+    let params = default_poseidon_config();
+    assert_eq!(
+        poseidon_params_hash(&params).unwrap(),
+        poseidon_params_hash(&poseidon_config(128, 2, 17).unwrap()).unwrap()
+    );
+    assert_eq!(params.ark.len(), 8 + 31);
+    assert!(params.ark.iter().all(|round| round.len() == 3));
+    assert_eq!(params.mds.len(), 3);
+    assert!(params.mds.iter().all(|row| row.len() == 3));
+
+    // 4. Sponge outputs under the default parameters, from arkworks' BLS12-381 Fr test
+    // (`test_poseidon_sponge_consistency` in ark-crypto-primitives 0.5.0,
+    // src/sponge/poseidon/mod.rs: rate 2, alpha 17, R_F = 8, R_P = 31, Grain LFSR constants)
+    let mut sponge = PoseidonSponge::<Fr>::new(&params);
+    sponge.absorb(&vec![Fr::from(0u8), Fr::from(1u8), Fr::from(2u8)]);
+    let expected = [
+        "40442793463571304028337753002242186710310163897048962278675457993207843616876",
+        "2664374461699898000291153145224099287711224021716202960480903840045233645301",
+        "50191078828066923662070228256530692951801504043422844038937334196346054068797",
+    ];
+    let squeezed: Vec<Fr> = sponge.squeeze_native_field_elements(3);
+    for (value, expected) in squeezed.iter().zip(expected) {
+        assert_eq!(value.to_string(), expected);
+    }
+}
+
+/// Builds a tree with backend `H` and checks `UserPullCircuit<H>` accepts the valid witness and
+/// rejects a tampered one. Returns the constraint count.
+fn check_membership_with_hash<H: MerkleHash>(tree_size: usize, target_leaf_index: usize) -> usize {
+    let params = H::default_parameters();
+    let mut rng = test_rng();
+    let leaves: Vec<[Fr; 2]> = (0..tree_size)
+        .map(|_| [Fr::rand(&mut rng), Fr::rand(&mut rng)])
+        .collect();
+    let tree = MerkleTree::<HashMerkleConfig<H>>::new(&params, &params, &leaves)
+        .expect("Failed to build Merkle tree");
+    let [secret_key, item_id] = leaves[target_leaf_index];
+    let inputs = NativeGachaCircuitInputs::<H> {
+        merkle_root: tree.root(),
+        item_id,
+        secret_key,
+        native_merkle_path: tree.generate_proof(target_leaf_index).unwrap(),
+    };
+
+    // Native path verification agrees with the tree
+    assert!(inputs
+        .native_merkle_path
+        .verify(&params, &params, &inputs.merkle_root, [secret_key, item_id])
+        .unwrap());
+
+    let cs = ConstraintSystem::<ConstraintField>::new_ref();
+    UserPullCircuit::<H>::new(inputs.clone(), params.clone())
+        .generate_constraints(cs.clone())
+        .unwrap();
+    assert!(
+        cs.is_satisfied().unwrap(),
+        "{} circuit not satisfied",
+        H::NAME
+    );
+    let num_constraints = cs.num_constraints();
+
+    let mut tampered = inputs;
+    tampered.item_id += Fr::from(1u64);
+    let cs = ConstraintSystem::<ConstraintField>::new_ref();
+    UserPullCircuit::<H>::new(tampered, params)
+        .generate_constraints(cs.clone())
+        .unwrap();
+    assert!(
+        !cs.is_satisfied().unwrap(),
+        "{} accepted a tampered leaf",
+        H::NAME
+    );
+
+    num_constraints
+}
+
+/// Round-trips `H`'s parameters and checks that every truncation of them is rejected on decode.
+/// Round counts are implied by the constants, so a decoded set can always be evaluated.
+#[cfg(any(feature = "poseidon2", feature = "rescue"))]
+fn check_parameters_round_trip<H: MerkleHash>() {
+    use ark_serialize::CanonicalDeserialize;
+
+    let params = H::default_parameters();
+    let mut bytes = Vec::new();
+    params.serialize_compressed(&mut bytes).unwrap();
+    let decoded = H::Parameters::deserialize_compressed(&bytes[..]).expect("Round trip failed");
+    let input = [Fr::from(1u64), Fr::from(2u64)];
+    assert_eq!(
+        H::LeafHash::evaluate(&decoded, input).unwrap(),
+        H::LeafHash::evaluate(&params, input).unwrap()
+    );
+    for len in 0..bytes.len() {
+        assert!(
+            H::Parameters::deserialize_compressed(&bytes[..len]).is_err(),
+            "{} accepted {} bytes",
+            H::NAME,
+            len
+        );
+    }
+}
+
+#[test]
+fn test_poseidon_hash_backend() {
+    let num_constraints = check_membership_with_hash::<PoseidonHash>(8, 5);
+
+    // The generic circuit is the deployed one: same constraints as the default type
+    let (inputs, _) = create_test_data(&setup_poseidon_params(), 8, 5).unwrap();
+    let cs = ConstraintSystem::<ConstraintField>::new_ref();
+    UserPullCircuit::new(inputs, setup_poseidon_params())
+        .generate_constraints(cs.clone())
+        .unwrap();
+    assert_eq!(cs.num_constraints(), num_constraints);
+}
+
+#[cfg(feature = "poseidon2")]
+#[test]
+fn test_poseidon2_hash_backend() {
+    let num_constraints = check_membership_with_hash::<Poseidon2Hash>(8, 5);
+    check_parameters_round_trip::<Poseidon2Hash>();
+    println!(
+        "Poseidon2 membership constraints (8 leaves): {}",
+        num_constraints
+    );
+}
+
+#[cfg(feature = "rescue")]
+#[test]
+fn test_rescue_prime_hash_backend() {
+    let num_constraints = check_membership_with_hash::<RescuePrimeHash>(8, 5);
+    check_parameters_round_trip::<RescuePrimeHash>();
+    println!(
+        "Rescue-Prime membership constraints (8 leaves): {}",
+        num_constraints
+    );
+}
+
+#[test]
+fn test_quaternary_merkle_tree() {
+    let params = QuaternaryMerkleParams::default();
+    let mut rng = test_rng();
+    let leaves: Vec<[Fr; 2]> = (0..64)
+        .map(|_| [Fr::rand(&mut rng), Fr::rand(&mut rng)])
+        .collect();
+    let tree = QuaternaryMerkleTree::new(&params, &leaves).expect("Failed to build tree");
+    assert_eq!(tree.depth(), 3);
+    assert!(
+        QuaternaryMerkleTree::new(&params, &leaves[..32]).is_err(),
+        "32 is not a power of 4"
+    );
+
+    // 1. Native paths verify for every position, and only for their own leaf
+    for index in [0, 1, 2, 3, 22, 63] {
+        let path = tree.generate_proof(index).unwrap();
+        assert!(path.verify(&params, &tree.root(), &leaves[index]).unwrap());
+        assert!(!path
+            .verify(&params, &tree.root(), &leaves[(index + 1) % 64])
+            .unwrap());
+    }
+
+    // 2. The circuit accepts a valid witness and rejects a tampered one
+    let target_leaf_index = 22;
+    let [secret_key, item_id] = leaves[target_leaf_index];
+    let inputs = QuaternaryGachaCircuitInputs {
+        merkle_root: tree.root(),
+        item_id,
+        secret_key,
+        merkle_path: tree.generate_proof(target_leaf_index).unwrap(),
+    };
+    let cs = ConstraintSystem::<ConstraintField>::new_ref();
+    QuaternaryUserPullCircuit::new(inputs.clone(), params.clone())
+        .generate_constraints(cs.clone())
+        .unwrap();
+    assert!(
+        cs.is_satisfied().unwrap(),
+        "Quaternary circuit not satisfied"
+    );
+
+    let mut tampered = inputs;
+    tampered.merkle_path.leaf_index = 23;
+    let cs = ConstraintSystem::<ConstraintField>::new_ref();
+    QuaternaryUserPullCircuit::new(tampered, params.clone())
+        .generate_constraints(cs.clone())
+        .unwrap();
+    assert!(
+        !cs.is_satisfied().unwrap(),
+        "Quaternary circuit accepted a wrong position"
+    );
+
+    // 3. Constraint counts against the binary tree for the same pool sizes
+    for tree_height in [4, 8, 16] {
+        let cs = ConstraintSystem::<ConstraintField>::new_ref();
+        UserPullCircuit::new(
+            NativeGachaCircuitInputs::<PoseidonHash>::placeholder(tree_height),
+            setup_poseidon_params(),
+        )
+        .generate_constraints(cs.clone())
+        .unwrap();
+        let binary = cs.num_constraints();
+
+        let cs = ConstraintSystem::<ConstraintField>::new_ref();
+        QuaternaryUserPullCircuit::new(
+            QuaternaryGachaCircuitInputs::placeholder(quaternary_depth(tree_height).unwrap()),
+            params.clone(),
+        )
+        .generate_constraints(cs.clone())
+        .unwrap();
+        let quaternary = cs.num_constraints();
+
+        println!(
+            "2^{} leaves: binary {} constraints, quaternary {} constraints",
+            tree_height, binary, quaternary
+        );
+        assert!(quaternary < binary);
+    }
+}
+
+#[test]
+fn test_constraint_budget() {
+    let budget: serde_json::Value =
+        serde_json::from_str(include_str!("constraint_budget.json")).unwrap();
+    let budgets = budget["budgets"].as_array().unwrap();
+
+    let mut heights: Vec<u64> = budgets
+        .iter()
+        .map(|entry| entry["treeHeight"].as_u64().unwrap())
+        .collect();
+    heights.dedup();
+    for height in heights {
+        for profile in profile_circuits(height as usize).unwrap() {
+            // Namespaces cover every constraint a circuit adds
+            let namespaced: usize = profile.namespaces.iter().map(|ns| ns.constraints).sum();
+            assert_eq!(namespaced, profile.constraints, "{}", profile.circuit);
+
+            let entry = budgets
+                .iter()
+                .find(|entry| {
+                    entry["circuit"] == profile.circuit.as_str() && entry["treeHeight"] == height
+                })
+                .unwrap_or_else(|| {
+                    panic!(
+                        "No budget for {} at height {}; add it to tests/constraint_budget.json",
+                        profile.circuit, height
+                    )
+                });
+            for (field, actual) in [
+                ("constraints", profile.constraints),
+                ("privateVariables", profile.private_variables),
+            ] {
+                let limit = entry[field].as_u64().unwrap() as usize;
+                assert!(
+                    actual <= limit,
+                    "{} at height {}: {} {} exceeds the budget of {}",
+                    profile.circuit,
+                    height,
+                    actual,
+                    field,
+                    limit
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_native_prove_pulls() {
+    let params = setup_poseidon_params();
+    let tree_size = 8;
+    let (pk, _vk, pvk) = setup_groth16_keys(&params, tree_size, 0).unwrap();
+    let pulls: Vec<_> = [1, 6]
+        .into_iter()
+        .map(|index| create_test_data(&params, tree_size, index).unwrap())
+        .collect();
+    let inputs: Vec<_> = pulls.iter().map(|(inputs, _)| inputs.clone()).collect();
+
+    // Same seed, same proofs, however the work was scheduled
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(7);
+    let proofs = prove_pulls(&pk, inputs.clone(), &params, &mut rng).unwrap();
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(7);
+    assert_eq!(prove_pulls(&pk, inputs, &params, &mut rng).unwrap(), proofs);
+
+    for (proof, (_, merkle_root)) in proofs.iter().zip(&pulls) {
+        let public_inputs = prepare_groth16_public_inputs(*merkle_root);
+        assert!(
+            Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &public_inputs, proof).unwrap()
+        );
+    }
+    println!(
+        "Proved {} pulls with {} thread(s)",
+        proofs.len(),
+        num_threads()
+    );
+
+    // The phased prover reports every phase in order and matches Groth16::prove
+    let (inputs, merkle_root) = pulls[0].clone();
+    let mut phases = Vec::new();
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(8);
+    let proof = prove_pull_with_progress(&pk, inputs.clone(), &params, &mut rng, |phase| {
+        phases.push(phase);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(phases, ProvePhase::ALL[..ProvePhase::ALL.len() - 1]);
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(8);
+    let circuit = UserPullCircuit::new(inputs.clone(), params.clone());
+    assert_eq!(
+        Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).unwrap(),
+        proof
+    );
+    let public_inputs = prepare_groth16_public_inputs(merkle_root);
+    assert!(Groth16::<Bls12_381>::verify_with_processed_vk(&pvk, &public_inputs, &proof).unwrap());
+
+    // An error from the callback cancels proving at that phase
+    let mut last_phase = None;
+    let cancelled = prove_pull_with_progress(&pk, inputs, &params, &mut rng, |phase| {
+        last_phase = Some(phase);
+        if phase == ProvePhase::MsmB {
+            return Err(GachaCircuitError::Cancelled);
+        }
+        Ok(())
+    });
+    assert!(matches!(cancelled, Err(GachaCircuitError::Cancelled)));
+    assert_eq!(last_phase, Some(ProvePhase::MsmB));
+
+    // A witness off its Merkle root, or for a tree the key was not made for, is rejected
+    let (mut bad_inputs, _) = pulls[1].clone();
+    bad_inputs.item_id += Fr::from(1u64);
+    assert!(matches!(
+        prove_pull(&pk, bad_inputs, &params, &mut rng),
+        Err(GachaCircuitError::InvalidInput(_))
+    ));
+    let (taller_inputs, _) = create_test_data(&params, tree_size * 2, 3).unwrap();
+    assert!(matches!(
+        prove_pulls(&pk, vec![taller_inputs], &params, &mut rng),
+        Err(GachaCircuitError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn test_native_api() {
+    let params = setup_poseidon_params();
+    let tree_size: usize = 8;
+    let (pk, vk, _pvk) = setup_groth16_keys(&params, tree_size, 0).unwrap();
+    let keys = GachaKeys {
+        proving_key: pk.clone(),
+        verifying_key: vk,
+        poseidon_params: params.clone(),
+        tree_height: tree_size.trailing_zeros() as usize,
+    };
+
+    // 1. Provers and verifiers are built from keys in memory; see `test_native_load` for files
+    let prover = native::GachaProver::new(keys.clone()).unwrap();
+    let verifier = native::GachaVerifier::new(VerifierKeys {
+        verifying_key: keys.verifying_key,
+        poseidon_params: keys.poseidon_params,
+        tree_height: keys.tree_height,
+    })
+    .unwrap();
+    assert_eq!(prover.tree_height(), 3);
+    assert_eq!(verifier.tree_height(), 3);
+
+    // 2. Prove and verify without touching Groth16 directly
+    let (inputs, merkle_root) = create_test_data(&params, tree_size, 5).unwrap();
+    let proof = prover.prove(&inputs).unwrap();
+    assert!(verifier.verify(merkle_root, &proof).unwrap());
+    assert!(prover.verifier().verify(merkle_root, &proof).unwrap());
+    assert!(!verifier
+        .verify(merkle_root + Fr::from(1u64), &proof)
+        .unwrap());
+    let batch = verifier
+        .verify_batch(&[merkle_root, Fr::from(1u64)], &[proof.clone(), proof])
+        .unwrap();
+    assert_eq!(batch.invalid_indices, vec![1]);
+
+    // 3. A seeded prove matches Groth16::prove, and bad witnesses fail before proving
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(5);
+    let proof = prover.prove_with_rng(&inputs, &mut rng).unwrap();
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(5);
+    let circuit = UserPullCircuit::new(inputs.clone(), params.clone());
+    assert_eq!(
+        Groth16::<Bls12_381>::prove(&pk, circuit, &mut rng).unwrap(),
+        proof
+    );
+    let mut wrong_item = inputs.clone();
+    wrong_item.item_id += Fr::from(1u64);
+    assert!(matches!(
+        prover.prove(&wrong_item),
+        Err(GachaCircuitError::InvalidInput(_))
+    ));
+
+    // 4. Provers need a proving key; verifiers work on any pool
+    let verifier_pool = verifier.pool().clone();
+    assert!(!verifier_pool.can_prove());
+    assert!(native::GachaProver::from_pool(verifier_pool).is_err());
+    let shared = native::GachaVerifier::from_pool(prover.pool().clone());
+    assert!(shared
+        .verify(merkle_root, &prover.prove(&inputs).unwrap())
+        .unwrap());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_native_load() {
+    let params = setup_poseidon_params();
+    let tree_size: usize = 8;
+    let (pk, vk, _pvk) = setup_groth16_keys(&params, tree_size, 0).unwrap();
+    let keys = GachaKeys {
+        proving_key: pk,
+        verifying_key: vk,
+        poseidon_params: params,
+        tree_height: tree_size.trailing_zeros() as usize,
+    };
+
+    // Keys load from a directory laid out like the `generate_crs` output
+    let dir = std::env::temp_dir().join(format!("zk_circuits_native_load_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let encoded = keys.encode().unwrap();
+    for (kind, bytes) in [
+        (KeyFileKind::ProvingKey, &encoded.proving_key),
+        (KeyFileKind::VerifyingKey, &encoded.verifying_key),
+        (KeyFileKind::PoseidonParams, &encoded.poseidon_params),
+    ] {
+        std::fs::write(dir.join(kind.file_name()), bytes).unwrap();
+    }
+    let prover = native::GachaProver::load(&dir).unwrap();
+    let verifier = native::GachaVerifier::load(&dir).unwrap();
+    assert_eq!(prover.tree_height(), 3);
+    assert!(!verifier.pool().can_prove());
+    std::fs::remove_file(dir.join(KeyFileKind::ProvingKey.file_name())).unwrap();
+    assert!(matches!(
+        GachaKeys::load(&dir),
+        Err(GachaCircuitError::InvalidKeyFile(message)) if message.contains("gacha_pk.bin")
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_pool_registry() {
+    let params = setup_poseidon_params();
+    let tree_size: usize = 8;
+    let tree_height = tree_size.trailing_zeros() as usize;
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(11);
+
+    // 1. Two pools ("seasons") with keys from independent setups
+    let mut encoded_keys = Vec::new();
+    for _ in 0..2 {
+        let circuit = UserPullCircuit::<PoseidonHash>::new(
+            NativeGachaCircuitInputs::placeholder(tree_height),
+            params.clone(),
+        );
+        let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(circuit, &mut rng).unwrap();
+        let keys = GachaKeys {
+            proving_key: pk,
+            verifying_key: vk,
+            poseidon_params: params.clone(),
+            tree_height,
+        };
+        encoded_keys.push(keys.encode().expect("Encoding keys failed"));
+    }
+    let decode = |i: usize, with_pk: bool| {
+        let encoded = &encoded_keys[i];
+        GachaPool::decode(
+            with_pk.then_some(encoded.proving_key.as_slice()),
+            &encoded.verifying_key,
+            &encoded.poseidon_params,
+            DeserializationPolicy::Checked,
+        )
+        .expect("Decoding pool failed")
+    };
+
+    let mut registry = PoolRegistry::new();
+    registry.load("banner-a", decode(0, true)).unwrap();
+    registry.load("banner-b", decode(1, false)).unwrap();
+    assert!(matches!(
+        registry.load("banner-a", decode(0, true)),
+        Err(GachaCircuitError::SetupError(_))
+    ));
+    assert_eq!(registry.pool_ids(), vec!["banner-a", "banner-b"]);
+
+    // 2. A proof verifies under its own pool only
+    let (inputs, merkle_root) = create_test_data(&params, tree_size, 3).unwrap();
+    let pool_a = registry.get("banner-a").unwrap();
+    assert!(pool_a.can_prove());
+    assert_eq!(pool_a.tree_height(), tree_height);
+    let proof = pool_a
+        .prove(inputs.clone(), &mut rng)
+        .expect("Proving failed");
+    assert!(pool_a.verify(merkle_root, &proof).unwrap());
+    let pool_b = registry.get("banner-b").unwrap();
+    assert!(!pool_b.verify(merkle_root, &proof).unwrap());
+
+    // 3. A verifier-only pool cannot prove
+    assert!(!pool_b.can_prove());
+    assert!(matches!(
+        pool_b.prove(inputs.clone(), &mut rng),
+        Err(GachaCircuitError::SetupError(_))
+    ));
+
+    // 4. Replacing a pool swaps its keys; handles to the old pool keep working
+    assert!(registry.replace("banner-a", decode(1, true)).is_some());
+    assert!(!registry
+        .get("banner-a")
+        .unwrap()
+        .verify(merkle_root, &proof)
+        .unwrap());
+    assert!(pool_a.verify(merkle_root, &proof).unwrap());
+
+    // 5. Unloaded pools are gone
+    assert!(registry.unload("banner-b").is_some());
+    assert!(registry.unload("banner-b").is_none());
+    assert!(matches!(
+        registry.get("banner-b"),
+        Err(GachaCircuitError::PoolNotFound(_))
+    ));
+    assert_eq!(registry.pool_ids(), vec!["banner-a"]);
+
+    // 6. The JS classes own the same kind of pool
+    #[cfg(feature = "wasm")]
+    {
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        let root_hex = fr_to_hex(&merkle_root).unwrap();
+        let verifier = JsGachaVerifier::new(
+            &encoded_keys[0].verifying_key,
+            &encoded_keys[0].poseidon_params,
+        )
+        .unwrap();
+        assert!(verifier.verify(&root_hex, &proof_bytes).unwrap());
+        let prover = JsGachaProver::new(
+            &encoded_keys[1].proving_key,
+            &encoded_keys[1].verifying_key,
+            &encoded_keys[1].poseidon_params,
+        )
+        .unwrap();
+        assert_eq!(prover.tree_height(), tree_height);
+        assert!(!prover.verifier().verify(&root_hex, &proof_bytes).unwrap());
+    }
+}
+
+#[test]
+fn test_proof_envelope() {
+    let params = setup_poseidon_params();
+    let tree_size: usize = 8;
+    let target_leaf_index: usize = 5;
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(13);
+
+    // 1. Two pools with keys from independent setups
+    let tree_height = tree_size.trailing_zeros() as usize;
+    let mut pools = Vec::new();
+    for _ in 0..2 {
+        let circuit = UserPullCircuit::<PoseidonHash>::new(
+            NativeGachaCircuitInputs::placeholder(tree_height),
+            params.clone(),
+        );
+        let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(circuit, &mut rng).unwrap();
+        let keys = GachaKeys {
+            proving_key: pk,
+            verifying_key: vk,
+            poseidon_params: params.clone(),
+            tree_height,
+        };
+        pools.push(GachaPool::from_keys(keys).expect("Building pool failed"));
+    }
+    let (inputs, merkle_root) = create_test_data(&params, tree_size, target_leaf_index).unwrap();
+    let proof = pools[0].prove(inputs, &mut rng).expect("Proving failed");
+    let envelope = GachaProofEnvelope::new_user_pull(
+        "banner-a",
+        *pools[0].vk_fingerprint(),
+        merkle_root,
+        proof,
+    );
+    assert_eq!(envelope.merkle_root().unwrap(), merkle_root);
+
+    // 2. Both encodings round-trip
+    let bytes = envelope.to_bytes().unwrap();
+    assert_eq!(&bytes[..ENVELOPE_MAGIC.len()], &ENVELOPE_MAGIC);
+    let decoded = GachaProofEnvelope::from_bytes(&bytes, DeserializationPolicy::Checked).unwrap();
+    assert_eq!(decoded, envelope);
+    let json = envelope.to_json().unwrap();
+    assert!(json.contains("\"poolId\":\"banner-a\""));
+    let decoded = GachaProofEnvelope::from_json(&json, DeserializationPolicy::Checked).unwrap();
+    assert_eq!(decoded, envelope);
+
+    // 3. The envelope verifies without being told the root, under its own keys only
+    assert!(pools[0].verify_envelope(&decoded).unwrap());
+    assert!(matches!(
+        pools[1].verify_envelope(&decoded),
+        Err(GachaCircuitError::InvalidEnvelope(_))
+    ));
+    let mut other_circuit = envelope.clone();
+    other_circuit.circuit_id = "user-pull/quaternary".to_string();
+    assert!(matches!(
+        pools[0].verify_envelope(&other_circuit),
+        Err(GachaCircuitError::InvalidEnvelope(_))
+    ));
+
+    // 4. A tampered root fails verification rather than erroring
+    let mut tampered = envelope.clone();
+    tampered.public_inputs[0] += ConstraintField::from(1u64);
+    assert!(!pools[0].verify_envelope(&tampered).unwrap());
+
+    // 5. Malformed encodings are rejected
+    assert!(matches!(
+        GachaProofEnvelope::from_bytes(&bytes[1..], DeserializationPolicy::Checked),
+        Err(GachaCircuitError::InvalidEnvelope(_))
+    ));
+    let mut future_version = bytes.clone();
+    future_version[ENVELOPE_MAGIC.len()] = 2;
+    assert!(matches!(
+        GachaProofEnvelope::from_bytes(&future_version, DeserializationPolicy::Checked),
+        Err(GachaCircuitError::InvalidEnvelope(_))
+    ));
+    let truncated = &bytes[..bytes.len() - 1];
+    assert!(GachaProofEnvelope::from_bytes(truncated, DeserializationPolicy::Checked).is_err());
+    assert!(GachaProofEnvelope::from_json("{}", DeserializationPolicy::Checked).is_err());
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(matches!(
+        GachaProofEnvelope::from_bytes(&trailing, DeserializationPolicy::Checked),
+        Err(GachaCircuitError::InvalidEnvelope(_))
+    ));
+
+    // 6. Huge length prefixes are rejected before anything is allocated from them
+    let circuit_id_len = ENVELOPE_MAGIC.len() + 2;
+    let mut huge_id = bytes.clone();
+    huge_id[circuit_id_len..circuit_id_len + 8].copy_from_slice(&0xFFFF_FFF0u64.to_le_bytes());
+    assert!(matches!(
+        GachaProofEnvelope::from_bytes(&huge_id, DeserializationPolicy::Checked),
+        Err(GachaCircuitError::InvalidEnvelope(_))
+    ));
+    let public_inputs_len =
+        circuit_id_len + 8 + envelope.circuit_id.len() + 8 + envelope.pool_id.len() + 32;
+    assert_eq!(
+        bytes[public_inputs_len..public_inputs_len + 8],
+        1u64.to_le_bytes()
+    );
+    let mut huge_inputs = bytes.clone();
+    huge_inputs[public_inputs_len..public_inputs_len + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(matches!(
+        GachaProofEnvelope::from_bytes(&huge_inputs, DeserializationPolicy::Checked),
+        Err(GachaCircuitError::InvalidEnvelope(_))
+    ));
+
+    // 7. A public input above the field modulus is bad data, not a bad curve point
+    let mut over_modulus = bytes.clone();
+    over_modulus[public_inputs_len + 8..public_inputs_len + 40].fill(0xff);
+    assert!(matches!(
+        GachaProofEnvelope::from_bytes(&over_modulus, DeserializationPolicy::Checked),
+        Err(GachaCircuitError::Deserialization(_))
+    ));
+    let mut long_pool_id = envelope.clone();
+    long_pool_id.pool_id = "p".repeat(MAX_ID_LEN + 1);
+    assert!(long_pool_id.to_bytes().is_err());
+}
+
+#[test]
+fn test_witness_validation() {
+    let params = setup_poseidon_params();
+    let tree_size: usize = 8;
+    let tree_height = tree_size.trailing_zeros() as usize;
+
+    // 1. Correct inputs pass for every leaf position
+    for leaf_index in 0..tree_size {
+        let (inputs, _) = create_test_data(&params, tree_size, leaf_index).unwrap();
+        inputs
+            .validate(&params, tree_height)
+            .expect("Valid inputs were rejected");
+    }
+
+    // 2. Each kind of corruption is reported as invalid input
+    let (inputs, _) = create_test_data(&params, tree_size, 6).unwrap();
+    let invalid_input = |inputs: &NativeGachaCircuitInputs, tree_height: usize| match inputs
+        .validate(&params, tree_height)
+    {
+        Err(GachaCircuitError::InvalidInput(message)) => message,
+        other => panic!("Expected InvalidInput, got {:?}", other),
+    };
+    let mut wrong_item = inputs.clone();
+    wrong_item.item_id += Fr::from(1u64);
+    assert!(invalid_input(&wrong_item, tree_height).contains("does not belong to Merkle root"));
+    let mut wrong_sibling = inputs.clone();
+    wrong_sibling.native_merkle_path.auth_path[0] += Fr::from(1u64);
+    assert!(invalid_input(&wrong_sibling, tree_height).contains("does not belong to Merkle root"));
+    let mut wrong_index = inputs.clone();
+    wrong_index.native_merkle_path.leaf_index = 7;
+    assert!(invalid_input(&wrong_index, tree_height).contains("does not belong to Merkle root"));
+    let mut out_of_range = inputs.clone();
+    out_of_range.native_merkle_path.leaf_index = tree_size;
+    assert!(invalid_input(&out_of_range, tree_height).contains("out of range"));
+    assert!(invalid_input(&inputs, tree_height + 1).contains("needs 3"));
+
+    // 3. Pools check inputs before proving
+    let (pk, vk, _) = setup_groth16_keys(&params, tree_size, 0).unwrap();
+    let pool = GachaPool::from_keys(GachaKeys {
+        proving_key: pk,
+        verifying_key: vk,
+        poseidon_params: params.clone(),
+        tree_height,
+    })
+    .unwrap();
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(17);
+    assert!(matches!(
+        pool.prove(wrong_item, &mut rng),
+        Err(GachaCircuitError::InvalidInput(_))
+    ));
+    assert!(pool.prove(inputs, &mut rng).is_ok());
+}
+
+#[test]
+fn test_field_element_encodings() {
+    let mut rng = test_rng();
+    let modulus = <Fr as PrimeField>::MODULUS;
+    let max = Fr::from(-1i64);
+
+    // 1. Every encoding round-trips, including the extremes
+    let encodings = [
+        FieldEncoding::HexLe,
+        FieldEncoding::HexBe,
+        FieldEncoding::Decimal,
+        FieldEncoding::HexLeModOrder,
+    ];
+    for value in [Fr::from(0u64), Fr::from(1u64), max, Fr::rand(&mut rng)] {
+        for encoding in encodings {
+            let encoded = fr_encode(&value, encoding);
+            assert_eq!(
+                fr_decode(&encoded, encoding).unwrap(),
+                value,
+                "{:?}",
+                encoding
+            );
+        }
+        assert_eq!(
+            fr_encode(&value, FieldEncoding::HexLe),
+            fr_to_hex(&value).unwrap()
+        );
+    }
+    let one_be = format!("0x{}01", "00".repeat(31));
+    assert_eq!(fr_encode(&Fr::from(1u64), FieldEncoding::HexBe), one_be);
+    // Decimal matches arkworks' `Display`
+    assert_eq!(fr_encode(&max, FieldEncoding::Decimal), max.to_string());
+
+    // 2. Non-canonical values are rejected by the strict encodings
+    let modulus_le = hex::encode(modulus.to_bytes_le());
+    let invalid = |value: &str, encoding: FieldEncoding| {
+        matches!(
+            fr_decode(value, encoding),
+            Err(GachaCircuitError::InvalidInput(_) | GachaCircuitError::HexDecoding(_))
+        )
+    };
+    assert!(invalid(&modulus_le, FieldEncoding::HexLe));
+    assert!(invalid(
+        &hex::encode(modulus.to_bytes_be()),
+        FieldEncoding::HexBe
+    ));
+    assert!(invalid(&modulus.to_string(), FieldEncoding::Decimal));
+    assert!(invalid("0x01", FieldEncoding::HexLe));
+    assert!(invalid(&format!("{}00", modulus_le), FieldEncoding::HexLe));
+    assert!(invalid(
+        &format!("0x{}zz", "00".repeat(31)),
+        FieldEncoding::HexLe
+    ));
+    for decimal in ["", "-1", "+1", "1_000", "0x1", &"9".repeat(100)] {
+        assert!(invalid(decimal, FieldEncoding::Decimal), "{:?}", decimal);
+    }
+    // ... while the lenient decoder reduces them
+    assert_eq!(
+        fr_decode(&modulus_le, FieldEncoding::HexLeModOrder).unwrap(),
+        Fr::from(0u64)
+    );
+    assert_eq!(fr_from_hex("0x01").unwrap(), Fr::from(1u64));
+
+    // 3. Circuit inputs are strict by default and can name their encoding
+    let params = setup_poseidon_params();
+    let (inputs, _) = create_test_data(&params, 4, 2).unwrap();
+    let path = &inputs.native_merkle_path;
+    let wasm_inputs_json = |encoding: FieldEncoding, root: String| {
+        serde_json::json!({
+            "merkleRoot": root,
+            "itemIdHex": fr_encode(&inputs.item_id, encoding),
+            "secretKeyHex": fr_encode(&inputs.secret_key, encoding),
+            "merklePathNodesHex": path
+                .auth_path
+                .iter()
+                .map(|node| fr_encode(node, encoding))
+                .collect::<Vec<_>>(),
+            "leafSiblingHashHex": fr_encode(&path.leaf_sibling_hash, encoding),
+            "leafIndex": path.leaf_index,
+            "fieldEncoding": encoding,
+        })
+    };
+    for encoding in encodings {
+        let json = wasm_inputs_json(encoding, fr_encode(&inputs.merkle_root, encoding));
+        let wasm_inputs: WasmGachaCircuitInputs = serde_json::from_value(json).unwrap();
+        let native = NativeGachaCircuitInputs::try_from(wasm_inputs).unwrap();
+        assert_eq!(native.merkle_root, inputs.merkle_root);
+        assert_eq!(native.native_merkle_path.auth_path, path.auth_path);
+    }
+    let mut json = wasm_inputs_json(
+        FieldEncoding::HexLe,
+        fr_to_hex(&inputs.merkle_root).unwrap(),
+    );
+    json.as_object_mut().unwrap().remove("fieldEncoding");
+    let wasm_inputs: WasmGachaCircuitInputs = serde_json::from_value(json.clone()).unwrap();
+    assert_eq!(wasm_inputs.field_encoding, FieldEncoding::HexLe);
+    assert!(NativeGachaCircuitInputs::try_from(wasm_inputs).is_ok());
+    // A root padded with a stray byte is no longer silently reduced
+    json["merkleRoot"] = format!("{}00", fr_to_hex(&inputs.merkle_root).unwrap()).into();
+    let wasm_inputs: WasmGachaCircuitInputs = serde_json::from_value(json).unwrap();
+    assert!(matches!(
+        NativeGachaCircuitInputs::try_from(wasm_inputs),
+        Err(GachaCircuitError::InvalidFieldElement { field, .. }) if field == "merkleRoot"
+    ));
+}
+
+#[test]
+fn test_merkle_path_checks() {
+    let params = setup_poseidon_params();
+    let tree_size: usize = 8;
+    let tree_height = tree_size.trailing_zeros() as usize;
+    let (inputs, merkle_root) = create_test_data(&params, tree_size, 5).unwrap();
+
+    // 1. The leaf digest is the Poseidon hash of [secret_key, item_id]
+    let digest = leaf_digest::<PoseidonHash>(&params, inputs.secret_key, inputs.item_id).unwrap();
+    let expected =
+        PoseidonCRH::<ConstraintField>::evaluate(&params, [inputs.secret_key, inputs.item_id])
+            .unwrap();
+    assert_eq!(digest, expected);
+
+    // 2. An item file (no root) checks out against its root and not against another
+    let path = &inputs.native_merkle_path;
+    let item_json = serde_json::json!({
+        "itemIdHex": fr_to_hex(&inputs.item_id).unwrap(),
+        "secretKeyHex": fr_to_hex(&inputs.secret_key).unwrap(),
+        "merklePathNodesHex": path
+            .auth_path
+            .iter()
+            .map(|node| fr_to_hex(node).unwrap())
+            .collect::<Vec<_>>(),
+        "leafSiblingHashHex": fr_to_hex(&path.leaf_sibling_hash).unwrap(),
+        "leafIndex": path.leaf_index,
+    });
+    let verify = |root: Fr, tree_height: usize| {
+        let item: WasmMerkleLeafInputs = serde_json::from_value(item_json.clone()).unwrap();
+        let wasm_inputs = item.with_merkle_root(fr_to_hex(&root).unwrap());
+        NativeGachaCircuitInputs::try_from(wasm_inputs)
+            .unwrap()
+            .verify_merkle_path(&params, tree_height)
+    };
+    assert!(verify(merkle_root, tree_height).unwrap());
+    assert!(!verify(merkle_root + Fr::from(1u64), tree_height).unwrap());
+
+    // 3. A path of the wrong shape is an error rather than a mismatch
+    assert!(matches!(
+        verify(merkle_root, tree_height + 1),
+        Err(GachaCircuitError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn test_gacha_tree_builder() {
+    let params = setup_poseidon_params();
+    let tree_height = 3;
+    let mut rng = ark_std::rand::rngs::StdRng::seed_from_u64(23);
+
+    // 1. Leaves keep their positions, the rest is filled, and every path checks out
+    let leaves: Vec<GachaLeaf> = (0..5).map(|_| GachaLeaf::random(&mut rng)).collect();
+    let tree = GachaTree::build(&params, tree_height, leaves.clone(), &mut rng).unwrap();
+    assert_eq!(tree.leaves().len(), 1 << tree_height);
+    assert_eq!(&tree.leaves()[..5], &leaves[..]);
+    for index in 0..tree.leaves().len() {
+        let inputs = tree.circuit_inputs(index).unwrap();
+        inputs.validate(&params, tree_height).unwrap();
+        // The item file holds the same inputs
+        let item = tree.item_file(index).unwrap();
+        let wasm_inputs = item.with_merkle_root(fr_to_hex(&tree.root()).unwrap());
+        let from_file = NativeGachaCircuitInputs::try_from(wasm_inputs).unwrap();
+        assert_eq!(
+            from_file.native_merkle_path.auth_path,
+            inputs.native_merkle_path.auth_path
+        );
+    }
+    assert!(tree.circuit_inputs(1 << tree_height).is_err());
+    let too_many = vec![leaves[0]; 9];
+    assert!(GachaTree::build(&params, tree_height, too_many, &mut rng).is_err());
+
+    // 2. Item files use the `prepare_gacha_data` schema
+    let item_json = serde_json::to_value(tree.item_file(2).unwrap()).unwrap();
+    let mut keys: Vec<_> = item_json.as_object().unwrap().keys().cloned().collect();
+    keys.sort();
+    assert_eq!(
+        keys,
+        [
+            "itemIdHex",
+            "leafIndex",
+            "leafSiblingHashHex",
+            "merklePathNodesHex",
+            "secretKeyHex"
+        ]
+    );
+
+    // 3. The JS builder reads the tree height from params.bin
+    #[cfg(feature = "wasm")]
+    {
+        let params_bytes = encode_key_file(
+            KeyFileKind::PoseidonParams,
+            tree_height,
+            poseidon_params_hash(&params).unwrap(),
+            [0u8; 32],
+            &params,
+        )
+        .unwrap();
+        let mut builder = GachaTreeBuilder::new(&params_bytes).unwrap();
+        assert_eq!(builder.tree_height(), tree_height);
+        let leaf = leaves[0];
+        let secret_key_hex = fr_to_hex(&leaf.secret_key).unwrap();
+        assert_eq!(
+            builder
+                .add_leaf(&secret_key_hex, &fr_to_hex(&leaf.item_id).unwrap())
+                .unwrap(),
+            0
+        );
+        assert_eq!(builder.add_random_leaf().unwrap(), 1);
+        let output = builder.build().unwrap();
+        assert_eq!(output.tree_height, tree_height);
+        assert_eq!(output.items.len(), 2);
+        assert_eq!(output.items[0].secret_key_hex, secret_key_hex);
+        for item in output.items {
+            let inputs = item.with_merkle_root(output.merkle_root_hex.clone());
+            assert!(NativeGachaCircuitInputs::try_from(inputs)
+                .unwrap()
+                .verify_merkle_path(&params, tree_height)
+                .unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_error_codes() {
+    // 1. Decoding errors name the input that failed, by its JS name
+    let params = setup_poseidon_params();
+    let (inputs, _) = create_test_data(&params, 4, 1).unwrap();
+    let path = &inputs.native_merkle_path;
+    let mut nodes_hex: Vec<String> = path
+        .auth_path
+        .iter()
+        .map(|n| fr_to_hex(n).unwrap())
+        .collect();
+    nodes_hex[0] = format!("0x{}zz", "00".repeat(31));
+    let wasm_inputs = WasmGachaCircuitInputs {
+        merkle_root_hex: fr_to_hex(&inputs.merkle_root).unwrap(),
+        item_id_hex: fr_to_hex(&inputs.item_id).unwrap(),
+        secret_key_hex: fr_to_hex(&inputs.secret_key).unwrap(),
+        merkle_path_nodes_hex: nodes_hex,
+        leaf_sibling_hash_hex: fr_to_hex(&path.leaf_sibling_hash).unwrap(),
+        leaf_index: path.leaf_index,
+        field_encoding: FieldEncoding::HexLe,
+    };
+    let error = NativeGachaCircuitInputs::try_from(wasm_inputs).unwrap_err();
+    assert_eq!(error.code(), "INVALID_FIELD_ELEMENT");
+    assert_eq!(error.kind(), "InvalidFieldElement");
+    assert_eq!(
+        error.details(),
+        vec![("field", "merklePathNodesHex[0]".to_string())]
+    );
+    assert!(
+        error.message().contains("Invalid character"),
+        "{}",
+        error.message()
+    );
+
+    // 2. Other variants carry their own code and details
+    let error = PoolRegistry::new().get("season-2").unwrap_err();
+    assert_eq!(error.code(), "POOL_NOT_FOUND");
+    assert_eq!(error.details(), vec![("poolId", "season-2".to_string())]);
+    let error = GachaCircuitError::InvalidInput("bad".to_string());
+    assert_eq!(
+        (error.code(), error.kind()),
+        ("INVALID_INPUT", "InvalidInput")
+    );
+    assert!(error.details().is_empty());
+    assert_eq!(error.message(), "Invalid input provided: bad");
+    assert_eq!(GachaCircuitError::Cancelled.code(), "CANCELLED");
+}
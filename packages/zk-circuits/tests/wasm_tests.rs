@@ -1,5 +1,10 @@
+#![cfg(target_arch = "wasm32")]
+
+use js_sys::Reflect;
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_test::*;
 use zk_circuits::tests::*;
+use zk_circuits::GachaCircuitError;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
@@ -23,3 +28,25 @@ fn test_fibonacci_fast() {
     assert_eq!(fibonacci_fast(3), 2);
     assert_eq!(fibonacci_fast(10), 55);
 }
+
+#[wasm_bindgen_test]
+fn test_errors_are_js_errors_with_codes() {
+    let get = |target: &JsValue, key: &str| Reflect::get(target, &JsValue::from_str(key)).unwrap();
+
+    let error: JsValue = GachaCircuitError::PoolNotFound("season-2".to_string()).into();
+    let js_error = error.dyn_ref::<js_sys::Error>().expect("not an Error");
+    assert_eq!(String::from(js_error.name()), "GachaCircuitError");
+    assert_eq!(
+        String::from(js_error.message()),
+        "Pool not loaded: season-2"
+    );
+    assert_eq!(get(&error, "code").as_string().unwrap(), "POOL_NOT_FOUND");
+    assert_eq!(get(&error, "kind").as_string().unwrap(), "PoolNotFound");
+    let details = get(&error, "details");
+    assert_eq!(get(&details, "poolId").as_string().unwrap(), "season-2");
+
+    // Variants without details have no `details` property
+    let error: JsValue = GachaCircuitError::Cancelled.into();
+    assert_eq!(get(&error, "code").as_string().unwrap(), "CANCELLED");
+    assert!(get(&error, "details").is_undefined());
+}
@@ -0,0 +1,117 @@
+#![cfg(target_arch = "wasm32")]
+
+// Runs in a headless browser's dedicated worker: `wasm-pack test --headless --firefox --test worker_tests`
+
+use ark_bls12_381::{Bls12_381, Fr};
+use ark_crypto_primitives::merkle_tree::MerkleTree;
+use ark_ff::UniformRand;
+use ark_groth16::Groth16;
+use ark_snark::SNARK;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use js_sys::{ArrayBuffer, Object, Reflect, Uint8Array};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::*;
+use zk_circuits::{
+    circuit::UserPullCircuit,
+    hash::PoseidonHash,
+    keys::{EncodedGachaKeys, GachaKeys},
+    poseidon::default_poseidon_config,
+    types::{
+        fr_to_hex, FieldEncoding, GachaMerkleConfig, NativeGachaCircuitInputs,
+        WasmGachaCircuitInputs,
+    },
+    JsGachaVerifier, WorkerProver,
+};
+
+wasm_bindgen_test_configure!(run_in_dedicated_worker);
+
+const TREE_HEIGHT: usize = 2;
+
+/// Key files for a small tree plus the JSON inputs and hex root of one pull from it.
+fn setup() -> (EncodedGachaKeys, String, String) {
+    let params = default_poseidon_config();
+    let mut rng = StdRng::seed_from_u64(0);
+    let leaves: Vec<[Fr; 2]> = (0..1usize << TREE_HEIGHT)
+        .map(|_| [Fr::rand(&mut rng), Fr::rand(&mut rng)])
+        .collect();
+    let tree = MerkleTree::<GachaMerkleConfig>::new(&params, &params, &leaves).unwrap();
+    let path = tree.generate_proof(1).unwrap();
+    let [secret_key, item_id] = leaves[1];
+    let root_hex = fr_to_hex(&tree.root()).unwrap();
+    let inputs = WasmGachaCircuitInputs {
+        merkle_root_hex: root_hex.clone(),
+        item_id_hex: fr_to_hex(&item_id).unwrap(),
+        secret_key_hex: fr_to_hex(&secret_key).unwrap(),
+        merkle_path_nodes_hex: path
+            .auth_path
+            .iter()
+            .map(|node| fr_to_hex(node).unwrap())
+            .collect(),
+        leaf_sibling_hash_hex: fr_to_hex(&path.leaf_sibling_hash).unwrap(),
+        leaf_index: path.leaf_index,
+        field_encoding: FieldEncoding::HexLe,
+    };
+
+    let circuit = UserPullCircuit::<PoseidonHash>::new(
+        NativeGachaCircuitInputs::placeholder(TREE_HEIGHT),
+        params.clone(),
+    );
+    let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(circuit, &mut rng).unwrap();
+    let keys = GachaKeys {
+        proving_key: pk,
+        verifying_key: vk,
+        poseidon_params: params,
+        tree_height: TREE_HEIGHT,
+    };
+    (
+        keys.encode().unwrap(),
+        serde_json::to_string(&inputs).unwrap(),
+        root_hex,
+    )
+}
+
+/// A standalone `ArrayBuffer`, as received through `postMessage` with a transfer list.
+fn to_buffer(bytes: &[u8]) -> JsValue {
+    Uint8Array::from(bytes).buffer().into()
+}
+
+#[wasm_bindgen_test]
+fn test_worker_prover_from_transferred_buffers() {
+    let (keys, inputs_json, root_hex) = setup();
+
+    // Buffers and typed array views are both accepted
+    let prover = WorkerProver::new(
+        to_buffer(&keys.proving_key),
+        to_buffer(&keys.verifying_key),
+        Uint8Array::from(keys.poseidon_params.as_slice()).into(),
+    )
+    .unwrap();
+    assert_eq!(prover.tree_height(), TREE_HEIGHT);
+
+    // The proof comes back as its own transferable ArrayBuffer
+    let proof: ArrayBuffer = prover
+        .prove_serialized(&inputs_json, None, JsValue::UNDEFINED)
+        .unwrap();
+    let proof_bytes = Uint8Array::new(&proof).to_vec();
+    assert_eq!(proof.byte_length() as usize, proof_bytes.len());
+    let verifier = JsGachaVerifier::new(&keys.verifying_key, &keys.poseidon_params).unwrap();
+    assert!(verifier.verify(&root_hex, &proof_bytes).unwrap());
+
+    // A cancelled token stops proving before it starts
+    let token = Object::new();
+    Reflect::set(&token, &"cancelled".into(), &JsValue::TRUE).unwrap();
+    assert!(prover
+        .prove_serialized(&inputs_json, None, token.into())
+        .is_err());
+
+    // Malformed inputs and non-buffer keys are rejected
+    assert!(prover
+        .prove_serialized("{}", None, JsValue::UNDEFINED)
+        .is_err());
+    assert!(WorkerProver::new(
+        JsValue::from(1),
+        to_buffer(&keys.verifying_key),
+        to_buffer(&keys.poseidon_params),
+    )
+    .is_err());
+}
@@ -0,0 +1,23 @@
+#!/usr/bin/env bash
+# Builds the full and the verifier-only wasm packages and reports their sizes.
+# Fails if the verifier-only build is not smaller than the full one.
+set -euo pipefail
+cd "$(dirname "$0")"
+
+wasm-pack build --release --target web --out-dir ./pkg --no-pack
+wasm-pack build --release --target web --out-dir ./pkg-verifier --no-pack -- --no-default-features --features wasm
+
+full=pkg/zk_circuits_bg.wasm
+verifier=pkg-verifier/zk_circuits_bg.wasm
+size() { wc -c < "$1" | tr -d ' '; }
+gz_size() { gzip -9 -c "$1" | wc -c | tr -d ' '; }
+
+printf '%-10s %12s %12s\n' build bytes gzip
+printf '%-10s %12s %12s\n' full "$(size $full)" "$(gz_size $full)"
+printf '%-10s %12s %12s\n' verifier "$(size $verifier)" "$(gz_size $verifier)"
+echo "verifier/full: $(( 100 * $(size $verifier) / $(size $full) ))%"
+
+if [ "$(size $verifier)" -ge "$(size $full)" ]; then
+    echo "verifier-only build is not smaller than the full build" >&2
+    exit 1
+fi
@@ -0,0 +1,175 @@
+// scripts/src/bin/aggregate_proofs.rs
+
+use ark_bls12_381::Bls12_381;
+use ark_groth16::{Groth16, Proof, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_snark::SNARK;
+use clap::{Parser, Subcommand};
+use serde::Deserialize;
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use zk_circuits::{
+    aggregation::{aggregate_proofs, verify_aggregate_proof, AggregateProof, AggregationSrs},
+    keys::{decode_key_file, KeyFileKind},
+    types::{fr_decode, ConstraintField, FieldEncoding},
+    utils::{
+        get_rng,
+        serialization::{deserialize_compressed, DeserializationPolicy, ValueKind},
+    },
+};
+
+/// Aggregates a season's worth of pull proofs into one proof, and verifies such aggregates.
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Samples an aggregation SRS from OS randomness. Demo only: whoever runs this could keep
+    /// the secrets and forge aggregates, so production SRS files must come from a powers-of-tau
+    /// ceremony or a trusted party.
+    Setup {
+        #[arg(long, default_value_t = 1024)]
+        max_proofs: usize,
+        #[arg(long, default_value = "./output/aggregation_srs.bin")]
+        out: PathBuf,
+    },
+    /// Aggregates every proof listed in a manifest
+    Aggregate {
+        #[arg(long, default_value = "./output/aggregation_srs.bin")]
+        srs: PathBuf,
+        /// JSON array of `{ "merkleRoot": "0x..", "proofHex": "0x.." }`
+        #[arg(long)]
+        manifest: PathBuf,
+        #[arg(long, default_value = "./output/aggregate_proof.bin")]
+        out: PathBuf,
+    },
+    /// Verifies an aggregate proof against the roots in a manifest
+    Verify {
+        #[arg(long, default_value = "./output/aggregation_srs.bin")]
+        srs: PathBuf,
+        #[arg(long, default_value = "./output/gacha_vk.bin")]
+        vk: PathBuf,
+        #[arg(long)]
+        manifest: PathBuf,
+        #[arg(long, default_value = "./output/aggregate_proof.bin")]
+        proof: PathBuf,
+    },
+}
+
+#[derive(Deserialize, Debug)]
+struct ManifestEntry {
+    #[serde(rename = "merkleRoot")]
+    merkle_root: String,
+    #[serde(rename = "proofHex")]
+    proof_hex: String,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    match Cli::parse().command {
+        Command::Setup { max_proofs, out } => {
+            println!(
+                "Sampling aggregation SRS for up to {} proofs...",
+                max_proofs
+            );
+            let mut rng = get_rng(None)?;
+            let srs = AggregationSrs::setup(&mut rng, max_proofs);
+            save(&srs, &out)?;
+            println!(
+                "SRS for {} proofs saved to {}",
+                srs.max_proofs(),
+                out.display()
+            );
+        }
+        Command::Aggregate { srs, manifest, out } => {
+            let srs: AggregationSrs = load("Aggregation SRS", &srs)?;
+            let (roots, proofs) = load_manifest(&manifest)?;
+            println!("Aggregating {} proofs...", proofs.len());
+
+            let prover_srs = srs.prover_srs(proofs.len().next_power_of_two())?;
+            let aggregate = aggregate_proofs(&prover_srs, &roots, &proofs)?;
+            save(&aggregate, &out)?;
+            println!(
+                "Aggregate proof ({} rounds, {} bytes) saved to {}",
+                aggregate.rounds.len(),
+                aggregate.compressed_size(),
+                out.display()
+            );
+        }
+        Command::Verify {
+            srs,
+            vk,
+            manifest,
+            proof,
+        } => {
+            let srs: AggregationSrs = load("Aggregation SRS", &srs)?;
+            let (_, vk): (_, VerifyingKey<Bls12_381>) = decode_key_file(
+                KeyFileKind::VerifyingKey,
+                &std::fs::read(&vk)?,
+                DeserializationPolicy::Checked,
+            )?;
+            let pvk = Groth16::<Bls12_381>::process_vk(&vk)?;
+            let aggregate: AggregateProof = load("Aggregate proof", &proof)?;
+            let (roots, _) = load_manifest(&manifest)?;
+
+            let is_valid = verify_aggregate_proof(&srs.verifier_srs()?, &pvk, &roots, &aggregate)?;
+            if !is_valid {
+                return Err("Aggregate proof verification FAILED".into());
+            }
+            println!("Aggregate proof for {} pulls VERIFIED.", roots.len());
+        }
+    }
+    Ok(())
+}
+
+/// Merkle roots and proofs read from a manifest, in manifest order.
+type ManifestData = (Vec<ConstraintField>, Vec<Proof<Bls12_381>>);
+
+// --- Helper Functions ---
+fn load_manifest(path: &Path) -> Result<ManifestData, Box<dyn std::error::Error>> {
+    let entries: Vec<ManifestEntry> = serde_json::from_reader(File::open(path)?)?;
+    let mut roots = Vec::with_capacity(entries.len());
+    let mut proofs = Vec::with_capacity(entries.len());
+    for (i, entry) in entries.iter().enumerate() {
+        roots.push(
+            fr_decode(&entry.merkle_root, FieldEncoding::HexLe)
+                .map_err(|e| format!("Manifest entry {}: invalid Merkle root: {}", i, e))?,
+        );
+        let bytes = hex::decode(entry.proof_hex.trim_start_matches("0x"))?;
+        let proof: Proof<Bls12_381> = deserialize_compressed(
+            "Proof",
+            &bytes,
+            ValueKind::CurvePoints,
+            DeserializationPolicy::Checked,
+        )
+        .map_err(|e| format!("Manifest entry {}: invalid proof: {}", i, e))?;
+        proofs.push(proof);
+    }
+    Ok((roots, proofs))
+}
+
+/// Reads `what` from `path`; the file is untrusted, so every point is validated.
+fn load<T: CanonicalDeserialize>(what: &str, path: &Path) -> Result<T, Box<dyn std::error::Error>> {
+    let mut buffer = Vec::new();
+    File::open(path)?.read_to_end(&mut buffer)?;
+    Ok(deserialize_compressed(
+        what,
+        &buffer,
+        ValueKind::CurvePoints,
+        DeserializationPolicy::Checked,
+    )?)
+}
+
+fn save<T: CanonicalSerialize>(value: &T, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut buffer = Vec::new();
+    value.serialize_compressed(&mut buffer)?;
+    File::create(path)?.write_all(&buffer)?;
+    Ok(())
+}
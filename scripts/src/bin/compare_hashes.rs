@@ -0,0 +1,94 @@
+// scripts/src/bin/compare_hashes.rs
+
+use ark_crypto_primitives::{crh::CRHScheme, merkle_tree::MerkleTree};
+use ark_ff::UniformRand;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use ark_std::test_rng;
+use clap::Parser;
+use std::time::Instant;
+
+#[cfg(feature = "poseidon2")]
+use zk_circuits::hash::Poseidon2Hash;
+#[cfg(feature = "rescue")]
+use zk_circuits::hash::RescuePrimeHash;
+use zk_circuits::{
+    circuit::UserPullCircuit,
+    hash::{MerkleHash, PoseidonHash},
+    types::{ConstraintField, HashMerkleConfig, NativeGachaCircuitInputs},
+};
+use zk_gacha_scripts::config::TREE_HEIGHT;
+
+/// Compares Merkle hash backends by circuit size and native speed.
+/// Build with `--features poseidon2,rescue` to include the alternative backends.
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Cli {
+    /// Tree height (2^height leaves)
+    #[arg(long, default_value_t = TREE_HEIGHT)]
+    height: usize,
+    /// Native leaf hashes to time per backend
+    #[arg(long, default_value_t = 10_000)]
+    iterations: usize,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    println!(
+        "{:<14} {:>12} {:>12} {:>16} {:>16}",
+        "hash", "constraints", "witnesses", "hash (us/op)", "tree build (ms)"
+    );
+    compare::<PoseidonHash>(&cli)?;
+    #[cfg(feature = "poseidon2")]
+    compare::<Poseidon2Hash>(&cli)?;
+    #[cfg(feature = "rescue")]
+    compare::<RescuePrimeHash>(&cli)?;
+    Ok(())
+}
+
+fn compare<H: MerkleHash>(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let params = H::default_parameters();
+    let mut rng = test_rng();
+    let leaves: Vec<[ConstraintField; 2]> = (0..1usize << cli.height)
+        .map(|_| {
+            [
+                ConstraintField::rand(&mut rng),
+                ConstraintField::rand(&mut rng),
+            ]
+        })
+        .collect();
+
+    // Native speed: single leaf hashes and a whole tree
+    let start = Instant::now();
+    for leaf in leaves.iter().cycle().take(cli.iterations) {
+        H::LeafHash::evaluate(&params, leaf.as_slice())?;
+    }
+    let hash_micros = start.elapsed().as_secs_f64() * 1e6 / cli.iterations.max(1) as f64;
+
+    let start = Instant::now();
+    let tree = MerkleTree::<HashMerkleConfig<H>>::new(&params, &params, &leaves)?;
+    let tree_millis = start.elapsed().as_secs_f64() * 1e3;
+
+    // Circuit size for one pull
+    let [secret_key, item_id] = leaves[0];
+    let inputs = NativeGachaCircuitInputs::<H> {
+        merkle_root: tree.root(),
+        item_id,
+        secret_key,
+        native_merkle_path: tree.generate_proof(0)?,
+    };
+    let cs = ConstraintSystem::<ConstraintField>::new_ref();
+    UserPullCircuit::<H>::new(inputs, params).generate_constraints(cs.clone())?;
+    if !cs.is_satisfied()? {
+        return Err(format!("{} circuit is not satisfied", H::NAME).into());
+    }
+
+    println!(
+        "{:<14} {:>12} {:>12} {:>16.2} {:>16.2}",
+        H::NAME,
+        cs.num_constraints(),
+        cs.num_witness_variables(),
+        hash_micros,
+        tree_millis
+    );
+    Ok(())
+}
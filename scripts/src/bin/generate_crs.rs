@@ -1,14 +1,13 @@
 // scripts/src/bin/generate_crs.rs
 
 use ark_bls12_381::Bls12_381;
-use ark_crypto_primitives::sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig};
-use ark_ff::{PrimeField, UniformRand};
-use ark_groth16::{Groth16, ProvingKey, VerifyingKey};
-use ark_serialize::CanonicalSerialize;
+use ark_groth16::Groth16;
 use ark_snark::SNARK;
-use ark_std::{rand::{RngCore, SeedableRng}, test_rng};
-use std::{fs::File, path::PathBuf};
-use ark_std::Zero;
+use ark_std::{
+    rand::{RngCore, SeedableRng},
+    test_rng,
+};
+use std::{fs::File, io::Write, path::PathBuf};
 
 // Use the config module from the scripts crate OR declare locally if no lib.rs
 use zk_gacha_scripts::config::TREE_HEIGHT;
@@ -17,17 +16,25 @@ use zk_gacha_scripts::config::TREE_HEIGHT;
 // Import from the actual zk_circuits library
 use zk_circuits::{
     circuit::UserPullCircuit,
-    types::{ConstraintField, NativeGachaCircuitInputs, NativeMerklePath, NativePoseidonConfig},
+    keys::{GachaKeys, KeyFileKind},
+    poseidon::{default_poseidon_config, DEFAULT_ALPHA, DEFAULT_SECURITY_LEVEL},
+    types::{NativeGachaCircuitInputs, NativePoseidonConfig},
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("Generating CRS for ZK Gacha Game (Tree Height: {})...", TREE_HEIGHT);
+    println!(
+        "Generating CRS for ZK Gacha Game (Tree Height: {})...",
+        TREE_HEIGHT
+    );
 
     let output_dir = PathBuf::from("./output");
     std::fs::create_dir_all(&output_dir)?;
 
-    println!("Generating Poseidon parameters...");
-    let poseidon_params = generate_poseidon_parameters()?;
+    println!(
+        "Generating Poseidon parameters ({}-bit security, x^{})...",
+        DEFAULT_SECURITY_LEVEL, DEFAULT_ALPHA
+    );
+    let poseidon_params = default_poseidon_config();
 
     println!("Creating dummy circuit...");
     let dummy_circuit = create_dummy_circuit(poseidon_params.clone())?;
@@ -37,85 +44,45 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let (pk, vk) = Groth16::<Bls12_381>::circuit_specific_setup(dummy_circuit, &mut rng)?;
 
     println!("Saving parameters and keys...");
-    save_params(&poseidon_params, &output_dir.join("params.bin"))?;
-    save_pk(&pk, &output_dir.join("gacha_pk.bin"))?;
-    save_vk(&vk, &output_dir.join("gacha_vk.bin"))?;
-
-    println!( "CRS generation complete! Files saved to {}", output_dir.display() );
-    Ok(())
-}
-
-fn generate_poseidon_parameters() -> Result<NativePoseidonConfig, Box<dyn std::error::Error>> {
-    let rate = 2;
-    let capacity = 1;
-    let full_rounds = 8;
-    let partial_rounds = 31;
-    let alpha = 17;
-
-    let (ark, mds) = find_poseidon_ark_and_mds::<ConstraintField>(
-        ConstraintField::MODULUS_BIT_SIZE as u64,
-        rate + capacity,
-        full_rounds as u64,
-        partial_rounds as u64,
-        0,
+    let keys = GachaKeys {
+        proving_key: pk,
+        verifying_key: vk,
+        poseidon_params,
+        tree_height: TREE_HEIGHT,
+    };
+    let encoded = keys.encode()?;
+    // The file names `GachaKeys::load` expects
+    save_bytes(
+        &encoded.poseidon_params,
+        &output_dir.join(KeyFileKind::PoseidonParams.file_name()),
+    )?;
+    save_bytes(
+        &encoded.proving_key,
+        &output_dir.join(KeyFileKind::ProvingKey.file_name()),
+    )?;
+    save_bytes(
+        &encoded.verifying_key,
+        &output_dir.join(KeyFileKind::VerifyingKey.file_name()),
+    )?;
+
+    println!(
+        "CRS generation complete! Files saved to {}",
+        output_dir.display()
     );
-
-    Ok(PoseidonConfig { full_rounds, partial_rounds, alpha: alpha as u64, ark, mds, rate, capacity })
+    Ok(())
 }
 
 fn create_dummy_circuit(
     poseidon_params: NativePoseidonConfig,
 ) -> Result<UserPullCircuit, Box<dyn std::error::Error>> {
-    let mut rng = test_rng();
-
-    let merkle_root = ConstraintField::rand(&mut rng);
-    let item_id = ConstraintField::rand(&mut rng);
-    let secret_key = ConstraintField::rand(&mut rng);
-
-    // Construct dummy path based on TREE_HEIGHT from config
-    let tree_height = TREE_HEIGHT;
-    let leaf_index = 0;
-    // Verify path length expected by NativeMerklePath / circuit gadget
-    let auth_path_len = tree_height; // Assuming path includes sibling upwards
-    let auth_path_vec: Vec<ConstraintField> = (0..auth_path_len)
-        .map(|_| ConstraintField::rand(&mut rng))
-        .collect();
-    let leaf_sibling_hash = ConstraintField::rand(&mut rng);
-
-    // Adjust construction based on NativeMerklePath definition in zk_circuits::types
-    let native_merkle_path = NativeMerklePath {
-        leaf_index,
-        auth_path: auth_path_vec,
-        leaf_sibling_hash, // Placeholder for leaf sibling hash
-
-        // Adjust fields if necessary
-    };
-
-    let inputs = NativeGachaCircuitInputs {
-        merkle_root,
-        item_id,
-        secret_key,
-        native_merkle_path,
-    };
-
+    // Only the shape matters for setup; the path length must match TREE_HEIGHT
+    let inputs = NativeGachaCircuitInputs::placeholder(TREE_HEIGHT);
     Ok(UserPullCircuit::new(inputs, poseidon_params))
 }
 
 // --- Save Functions ---
-fn save_params( params: &NativePoseidonConfig, path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+fn save_bytes(bytes: &[u8], path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
     let mut file = File::create(path)?;
-    params.serialize_compressed(&mut file)?;
+    file.write_all(bytes)?;
     Ok(())
 }
-
-fn save_pk( pk: &ProvingKey<Bls12_381>, path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    let mut file = File::create(path)?;
-    pk.serialize_compressed(&mut file)?;
-    Ok(())
-}
-
-fn save_vk( vk: &VerifyingKey<Bls12_381>, path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    let mut file = File::create(path)?;
-    vk.serialize_compressed(&mut file)?;
-    Ok(())
-}
\ No newline at end of file
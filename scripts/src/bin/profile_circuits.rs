@@ -0,0 +1,48 @@
+// scripts/src/bin/profile_circuits.rs
+
+use clap::Parser;
+
+use zk_circuits::profiling::profile_circuits;
+use zk_gacha_scripts::config::TREE_HEIGHT;
+
+/// Prints constraint and variable counts of every circuit, with a per-namespace breakdown.
+#[derive(Parser, Debug)]
+#[command(author, version, about)]
+struct Cli {
+    /// Tree heights to profile (2^height leaves)
+    #[arg(long, num_args = 1.., default_values_t = [TREE_HEIGHT])]
+    height: Vec<usize>,
+    /// Print JSON instead of a table
+    #[arg(long)]
+    json: bool,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let mut profiles = Vec::new();
+    for &height in &cli.height {
+        profiles.extend(profile_circuits(height)?);
+    }
+
+    if cli.json {
+        println!("{}", serde_json::to_string_pretty(&profiles)?);
+        return Ok(());
+    }
+    for profile in &profiles {
+        println!(
+            "{} (height {}): {} constraints, {} public inputs, {} private variables",
+            profile.circuit,
+            profile.tree_height,
+            profile.constraints,
+            profile.public_inputs,
+            profile.private_variables
+        );
+        for ns in &profile.namespaces {
+            println!(
+                "  {:<24} {:>8} constraints {:>8} private variables",
+                ns.name, ns.constraints, ns.private_variables
+            );
+        }
+    }
+    Ok(())
+}